@@ -0,0 +1,199 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::cmp::Ord;
+use core::iter::Iterator;
+
+use inky_frame::frame::Color;
+
+use crate::draw::Canvas;
+use crate::pattern::{self, PALETTE, Seed, Shade};
+
+/// Number of generative art types that can be drawn.
+pub const COUNT: usize = 4usize;
+
+/// Max number of circles placed when circle packing.
+const MAX_CIRCLES: usize = 96usize;
+/// Number of random spots tried when circle packing.
+const CIRCLE_TRIES: i32 = 800i32;
+/// Max number of points in a Voronoi mosaic.
+const MAX_SITES: usize = 32usize;
+/// Widest display the Voronoi edges are drawn for.
+const MAX_WIDTH: usize = 800usize;
+
+/// Draw the generative art type 'kind' (less than 'COUNT') over the whole
+/// Canvas, with the colors and sizes picked using 'r'.
+///
+/// - 0: Noise field, smooth random noise split into dithered color bands.
+/// - 1: Truchet tiles, quarter circles that join into random paths.
+/// - 2: Circle packing, random circles that don't overlap.
+/// - 3: Voronoi mosaic, each pixel takes the color of it's closest point.
+pub fn draw(c: &mut impl Canvas, kind: usize, r: &mut Seed) {
+    match kind {
+        0 => noise(c, r),
+        1 => truchet(c, r),
+        2 => circles(c, r),
+        _ => voronoi(c, r),
+    }
+}
+
+fn noise(c: &mut impl Canvas, r: &mut Seed) {
+    // Cell sizes are powers of two, so the interpolation only needs shifts.
+    let (s, m, h) = (5 + r.next(2) as i32, 3 + r.next(3), r.next(usize::MAX) as u32);
+    let mut p = [Color::Black; 5];
+    let mut i = r.next(PALETTE.len());
+    for v in p[0..m].iter_mut() {
+        *v = PALETTE[i];
+        // Neighboring bands are always different colors.
+        i = (i + 1 + r.next(PALETTE.len() - 1)) % PALETTE.len();
+    }
+    pattern::each(c, |x, y| {
+        // Two octaves, the smaller one adds some detail.
+        let v = (value(h, x, y, s) * 2 + value(h ^ 0x5555_5555, x, y, s - 1)) / 3;
+        let t = v * (m as i32 - 1);
+        let b = (t >> 8) as usize;
+        Shade::mix(p[b], p[b + 1], ((t & 0xFF) >> 4) as u8).at(x, y)
+    })
+}
+fn truchet(c: &mut impl Canvas, r: &mut Seed) {
+    let (n, (a, b), h) = (4 + r.next(3) as i32, r.pair(), r.next(usize::MAX) as u32);
+    let s = 1 << n;
+    // Arcs are drawn where the distance from the corner is between 'i' and
+    // 'o', stored squared to skip the square root.
+    let t = (s / 8).max(2);
+    let (i, o) = ((s / 2 - t) * (s / 2 - t), (s / 2 + t) * (s / 2 + t));
+    pattern::each(c, |x, y| {
+        let (u, v) = (x & (s - 1), y & (s - 1));
+        // Flip half of the tiles to get the other orientation.
+        let u = if hash(h, x >> n, y >> n) & 1 == 0 { u } else { s - 1 - u };
+        // Arcs around the top left and bottom right corners.
+        let (d, e) = (u * u + v * v, (s - u) * (s - u) + (s - v) * (s - v));
+        if (i..=o).contains(&d) || (i..=o).contains(&e) { b } else { a }
+    })
+}
+fn circles(c: &mut impl Canvas, r: &mut Seed) {
+    let (w, h) = (c.width() as i32, c.height() as i32);
+    let g = r.next(PALETTE.len());
+    c.fill(PALETTE[g]);
+    let (mut p, mut n) = ([(0i32, 0i32, 0i32); MAX_CIRCLES], 0usize);
+    let (l, m) = (h / 4, 4i32);
+    for k in 0..CIRCLE_TRIES {
+        if n >= MAX_CIRCLES {
+            break;
+        }
+        // Start big and shrink, so the gaps get filled in.
+        let d = l - ((l - m) * k) / CIRCLE_TRIES;
+        let (x, y) = (r.next(w as usize) as i32, r.next(h as usize) as i32);
+        if p[0..n].iter().any(|(a, b, e)| (a - x) * (a - x) + (b - y) * (b - y) < (e + d + 2) * (e + d + 2)) {
+            continue;
+        }
+        p[n] = (x, y, d);
+        n += 1;
+        // Anything but the background color.
+        let v = (g + 1 + r.next(PALETTE.len() - 1)) % PALETTE.len();
+        disc(c, x, y, d, PALETTE[v]);
+        // Some of the larger ones get a ring.
+        if d > 12 && r.next(2) == 0 {
+            disc(c, x, y, (d * 2) / 3, PALETTE[(v + 1 + r.next(PALETTE.len() - 1)) % PALETTE.len()]);
+        }
+    }
+}
+fn voronoi(c: &mut impl Canvas, r: &mut Seed) {
+    let (w, h) = (c.width() as i32, c.height() as i32);
+    let n = 12 + r.next(MAX_SITES - 12);
+    let mut p = [(0i32, 0i32, Color::Black); MAX_SITES];
+    for v in p[0..n].iter_mut() {
+        *v = (r.next(w as usize) as i32, r.next(h as usize) as i32, PALETTE[r.next(PALETTE.len())]);
+    }
+    // Outline the cells, so cells with the same color can be told apart.
+    let e = r.next(2) == 0;
+    // Closest point for each pixel in the row above, for the edges.
+    let mut u = [0u8; MAX_WIDTH];
+    for y in 0..h {
+        let mut l = 0u8;
+        for x in 0..w {
+            let (mut k, mut d) = (0usize, i32::MAX);
+            for (i, (a, b, _)) in p[0..n].iter().enumerate() {
+                let v = (a - x) * (a - x) + (b - y) * (b - y);
+                if v < d {
+                    (k, d) = (i, v);
+                }
+            }
+            let i = x as usize;
+            let f = e && ((x > 0 && l != k as u8) || (y > 0 && i < MAX_WIDTH && u[i] != k as u8));
+            c.pixel(x, y, if f { Color::Black } else { p[k].2 });
+            l = k as u8;
+            if i < MAX_WIDTH {
+                u[i] = k as u8;
+            }
+        }
+    }
+}
+
+/// Fill a circle with it's center at (x, y) and a radius of 'r'.
+fn disc(c: &mut impl Canvas, x: i32, y: i32, r: i32, v: Color) {
+    for j in -r..=r {
+        let k = isqrt(r * r - j * j);
+        c.rect(x - k, y + j, (k * 2 + 1) as u16, 1, v);
+    }
+}
+#[inline]
+pub fn isqrt(v: i32) -> i32 {
+    if v <= 0 {
+        return 0;
+    }
+    let (mut x, mut y) = (v, (v + 1) / 2);
+    while y < x {
+        x = y;
+        y = (x + v / x) / 2;
+    }
+    x
+}
+/// Returns a random value (0 - 255) for the grid point (x, y).
+#[inline]
+fn hash(h: u32, x: i32, y: i32) -> u32 {
+    let mut v = h ^ (x as u32).wrapping_mul(0x27D4_EB2Du32) ^ (y as u32).wrapping_mul(0x1656_67B1u32);
+    v ^= v >> 15;
+    v = v.wrapping_mul(0x2C1B_3C6Du32);
+    v ^= v >> 12;
+    v = v.wrapping_mul(0x297A_2D39u32);
+    (v ^ (v >> 15)) & 0xFF
+}
+/// Returns the smooth noise value (0 - 255) at (x, y) for grid cells that are
+/// '1 << s' pixels wide.
+#[inline]
+fn value(h: u32, x: i32, y: i32, s: i32) -> i32 {
+    let (gx, gy, z) = (x >> s, y >> s, (1 << s) - 1);
+    let (fx, fy) = (fade(x & z, s), fade(y & z, s));
+    let (a, b) = (hash(h, gx, gy) as i32, hash(h, gx + 1, gy) as i32);
+    let (e, f) = (hash(h, gx, gy + 1) as i32, hash(h, gx + 1, gy + 1) as i32);
+    let (t, u) = (a + (((b - a) * fx) >> s), e + (((f - e) * fx) >> s));
+    t + (((u - t) * fy) >> s)
+}
+/// Smoothstep the cell position 'v' (0 - '1 << s'), so the cell edges are not
+/// visible.
+#[inline(always)]
+fn fade(v: i32, s: i32) -> i32 {
+    (v * v * ((3 << s) - 2 * v)) >> (2 * s)
+}
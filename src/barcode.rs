@@ -0,0 +1,224 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::iter::Iterator;
+use core::option::Option::{self, None, Some};
+
+use inky_frame::frame::Color;
+
+use crate::draw::Canvas;
+
+/// Max number of modules (narrowest bar widths) in a barcode. This is enough
+/// for about 80 Code 128 characters, which is more than will fit on the display.
+const MAX_MODULES: usize = 1_024usize;
+
+const CODE_B: u32 = 100u32;
+const CODE_C: u32 = 99u32;
+const START_B: u32 = 104u32;
+const START_C: u32 = 105u32;
+const STOP: u32 = 0x0233_1112u32;
+
+/// Code 128 symbols as the widths of their bars and spaces, one per nibble,
+/// starting with a bar.
+const CODE128: [u32; 106] = [
+    0x212222, 0x222122, 0x222221, 0x121223, 0x121322, 0x131222, 0x122213, 0x122312, 0x132212, 0x221213, // 0 - 9
+    0x221312, 0x231212, 0x112232, 0x122132, 0x122231, 0x113222, 0x123122, 0x123221, 0x223211, 0x221132, // 10 - 19
+    0x221231, 0x213212, 0x223112, 0x312131, 0x311222, 0x321122, 0x321221, 0x312212, 0x322112, 0x322211, // 20 - 29
+    0x212123, 0x212321, 0x232121, 0x111323, 0x131123, 0x131321, 0x112313, 0x132113, 0x132311, 0x211313, // 30 - 39
+    0x231113, 0x231311, 0x112133, 0x112331, 0x132131, 0x113123, 0x113321, 0x133121, 0x313121, 0x211331, // 40 - 49
+    0x231131, 0x213113, 0x213311, 0x213131, 0x311123, 0x311321, 0x331121, 0x312113, 0x312311, 0x332111, // 50 - 59
+    0x314111, 0x221411, 0x431111, 0x111224, 0x111422, 0x121124, 0x121421, 0x141122, 0x141221, 0x112214, // 60 - 69
+    0x112412, 0x122114, 0x122411, 0x142112, 0x142211, 0x241211, 0x221114, 0x413111, 0x241112, 0x134111, // 70 - 79
+    0x111242, 0x121142, 0x121241, 0x114212, 0x124112, 0x124211, 0x411212, 0x421112, 0x421211, 0x212141, // 80 - 89
+    0x214121, 0x412121, 0x111143, 0x111341, 0x131141, 0x114113, 0x114311, 0x411113, 0x411311, 0x113141, // 90 - 99
+    0x114131, 0x311141, 0x411131, 0x211412, 0x211214, 0x211232, // 100 - 105
+];
+/// EAN-13 'L' (odd parity) digit codes. The 'R' codes are the inverse and the
+/// 'G' (even parity) codes are the 'R' codes reversed.
+const EAN_L: [u8; 10] = [0x0D, 0x19, 0x13, 0x3D, 0x23, 0x31, 0x2F, 0x3B, 0x37, 0x0B];
+/// EAN-13 parity of the left digits, selected by the first digit. Set bits are
+/// 'G' codes, starting from the highest bit.
+const EAN_PARITY: [u8; 10] = [0x00, 0x0B, 0x0D, 0x0E, 0x13, 0x19, 0x1C, 0x15, 0x16, 0x1A];
+
+pub enum Symbology {
+    /// Code 128, any printable ASCII text. Runs of digits are packed using
+    /// Code C to keep the barcode short.
+    Code128,
+    /// EAN-13, 12 digits (the check digit is added) or 13 digits (the check
+    /// digit is verified).
+    Ean13,
+}
+
+/// Linear barcode, stored as a row of modules.
+pub struct Barcode {
+    buf: [u8; MAX_MODULES / 8],
+    len: u16,
+}
+
+impl Barcode {
+    /// Encode the string 's' as a barcode. Returns None if the string is empty,
+    /// has characters the Symbology can't encode or is too long.
+    pub fn encode(s: &str, t: &Symbology) -> Option<Barcode> {
+        let mut b = Barcode {
+            buf: [0u8; MAX_MODULES / 8],
+            len: 0u16,
+        };
+        match t {
+            Symbology::Code128 => b.code128(s.as_bytes())?,
+            Symbology::Ean13 => b.ean13(s.as_bytes())?,
+        }
+        Some(b)
+    }
+
+    /// Returns the width of the barcode, in modules, not including the quiet
+    /// zones.
+    #[inline(always)]
+    pub fn len(&self) -> u16 {
+        self.len
+    }
+    /// Draw the barcode with it's top left corner at (x, y), with each module
+    /// 'w' pixels wide and 'h' pixels tall. A light border 'quiet' modules wide
+    /// is drawn on both sides.
+    ///
+    /// Bars are drawn at whole pixel widths, so they scan reliably.
+    pub fn draw(&self, c: &mut impl Canvas, x: i32, y: i32, w: u16, h: u16, quiet: u16) {
+        let w = w.max(1);
+        c.rect(x, y, (self.len + quiet * 2) * w, h, Color::White);
+        let x = x + (quiet * w) as i32;
+        let mut i = 0u16;
+        while i < self.len {
+            if !self.get(i) {
+                i += 1;
+                continue;
+            }
+            // Draw each bar as one rect instead of per module.
+            let s = i;
+            while i < self.len && self.get(i) {
+                i += 1;
+            }
+            c.rect(x + (s * w) as i32, y, (i - s) * w, h, Color::Black);
+        }
+    }
+
+    #[inline(always)]
+    fn get(&self, i: u16) -> bool {
+        (self.buf[i as usize / 8] >> (i % 8)) & 1 != 0
+    }
+    #[inline]
+    fn push(&mut self, v: bool) -> Option<()> {
+        let i = self.len as usize;
+        if i >= MAX_MODULES {
+            return None;
+        }
+        if v {
+            self.buf[i / 8] |= 1 << (i % 8);
+        }
+        self.len += 1;
+        Some(())
+    }
+    #[inline]
+    fn bits(&mut self, v: u8, n: u8) -> Option<()> {
+        for i in (0..n).rev() {
+            self.push((v >> i) & 1 != 0)?;
+        }
+        Some(())
+    }
+    fn widths(&mut self, p: u32) -> Option<()> {
+        // The stop symbol has an extra bar.
+        let n = if p > 0xFF_FFFF { 7 } else { 6 };
+        for i in 0..n {
+            let w = (p >> ((n - 1 - i) * 4)) & 0xF;
+            for _ in 0..w {
+                self.push(i % 2 == 0)?;
+            }
+        }
+        Some(())
+    }
+    fn code128(&mut self, s: &[u8]) -> Option<()> {
+        if s.is_empty() || s.iter().any(|v| !(0x20..=0x7E).contains(v)) {
+            return None;
+        }
+        // Code C packs two digits per symbol, but switching to it costs a
+        // symbol, so only use it for longer runs of digits.
+        let r = digits(s, 0);
+        let mut c = r >= 4 || (r == s.len() && r % 2 == 0);
+        let v = if c { START_C } else { START_B };
+        self.widths(CODE128[v as usize])?;
+        let (mut i, mut k, mut n) = (0usize, v, 1u32);
+        while i < s.len() {
+            let v = if c {
+                if digits(s, i) >= 2 {
+                    i += 2;
+                    (s[i - 2] - b'0') as u32 * 10 + (s[i - 1] - b'0') as u32
+                } else {
+                    c = false;
+                    CODE_B
+                }
+            } else {
+                // Odd runs have their first digit in Code B.
+                let r = digits(s, i);
+                if r % 2 == 0 && (r >= 6 || (r >= 4 && i + r == s.len())) {
+                    c = true;
+                    CODE_C
+                } else {
+                    i += 1;
+                    (s[i - 1] - 0x20) as u32
+                }
+            };
+            self.widths(CODE128[v as usize])?;
+            k += v * n;
+            n += 1;
+        }
+        self.widths(CODE128[(k % 103) as usize])?;
+        self.widths(STOP)
+    }
+    fn ean13(&mut self, s: &[u8]) -> Option<()> {
+        if (s.len() != 12 && s.len() != 13) || digits(s, 0) != s.len() {
+            return None;
+        }
+        let d = |i: usize| (s[i] - b'0') as usize;
+        let c = (0..12).map(|i| d(i) * if i % 2 == 0 { 1 } else { 3 }).sum::<usize>();
+        let c = (10 - c % 10) % 10;
+        if s.len() == 13 && d(12) != c {
+            return None;
+        }
+        self.bits(0x5, 3)?;
+        let p = EAN_PARITY[d(0)];
+        for i in 1..7 {
+            let v = EAN_L[d(i)];
+            self.bits(if (p >> (6 - i)) & 1 != 0 { (!v & 0x7F).reverse_bits() >> 1 } else { v }, 7)?;
+        }
+        self.bits(0xA, 5)?;
+        for i in 7..13 {
+            self.bits(!EAN_L[if i == 12 { c } else { d(i) }] & 0x7F, 7)?;
+        }
+        self.bits(0x5, 3)
+    }
+}
+
+/// Returns the number of ASCII digits in a row starting at 'i'.
+#[inline]
+fn digits(s: &[u8], i: usize) -> usize {
+    s[i..].iter().take_while(|v| v.is_ascii_digit()).count()
+}
@@ -0,0 +1,209 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate rpsp;
+
+use core::cmp::Ord;
+use core::fmt::{self, Write};
+use core::iter::Iterator;
+use core::option::Option::{self, None, Some};
+use core::result::Result::Ok;
+use core::write;
+
+use rpsp::time::Time;
+
+/// Days between 0000-03-01 and 2000-01-01, used to move the epoch.
+const EPOCH_DAYS: i32 = 730_425i32;
+
+const DAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Simple calendar date, with the month and day starting at one.
+pub struct Date {
+    pub year:  u16,
+    pub month: u8,
+    pub day:   u8,
+}
+
+impl Date {
+    #[inline(always)]
+    pub const fn new(year: u16, month: u8, day: u8) -> Date {
+        Date { year, month, day }
+    }
+    #[inline(always)]
+    pub fn from_time(t: &Time) -> Date {
+        Date::new(t.year, t.month as u8, t.day)
+    }
+
+    /// Parse a date in the "YYYY-MM-DD" format.
+    pub fn parse(s: &str) -> Option<Date> {
+        let mut v = s.split('-');
+        let y = v.next()?.parse::<u16>().ok()?;
+        let (m, d) = (v.next()?.parse::<u8>().ok()?, v.next()?.parse::<u8>().ok()?);
+        if v.next().is_some() || m == 0 || m > 12 || d == 0 || d > 31 {
+            return None;
+        }
+        Some(Date::new(y, m, d))
+    }
+
+    /// Returns the number of days since 2000-01-01.
+    pub fn days(&self) -> i32 {
+        // Based on the 'days_from_civil' algorithm by Howard Hinnant.
+        let y = self.year as i32 - if self.month <= 2 { 1 } else { 0 };
+        let e = y.div_euclid(400);
+        let a = y - e * 400;
+        let m = self.month as i32;
+        let d = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + self.day as i32 - 1;
+        let o = a * 365 + a / 4 - a / 100 + d;
+        e * 146_097 + o - EPOCH_DAYS
+    }
+    /// Returns the day of the week, with Sunday as zero.
+    #[inline]
+    pub fn weekday(&self) -> u8 {
+        // 2000-01-01 was a Saturday.
+        (self.days() + 6).rem_euclid(7) as u8
+    }
+}
+
+/// Returns the number of seconds since 2000-01-01 00:00:00.
+#[inline]
+pub fn timestamp(t: &Time) -> u32 {
+    seconds(&Date::from_time(t), t.hours, t.mins, t.secs)
+}
+/// Returns the number of seconds since 2000-01-01 00:00:00 for the provided
+/// date and time.
+#[inline]
+pub fn seconds(d: &Date, hours: u8, mins: u8, secs: u8) -> u32 {
+    (d.days().max(0) as u32 * 86_400u32) + (hours as u32 * 3_600u32) + (mins as u32 * 60u32) + secs as u32
+}
+/// Returns the number of seconds since 2000-01-01 00:00:00 for the date string
+/// 's', in the "YYYY-MM-DD" or "YYYY-MM-DD HH:MM" format.
+pub fn parse(s: &str) -> Option<u32> {
+    let (d, t) = match s.split_once(' ') {
+        Some((d, t)) => (d, Some(t.trim())),
+        None => (s, None),
+    };
+    let (h, m) = match t {
+        Some(v) => {
+            let (h, m) = v.split_once(':')?;
+            (h.parse::<u8>().ok()?, m.parse::<u8>().ok()?)
+        },
+        None => (0, 0),
+    };
+    if h > 23 || m > 59 {
+        return None;
+    }
+    Some(seconds(&Date::parse(d)?, h, m, 0))
+}
+/// Returns true if the date 'd' is in 'v', which is a single date or a range of
+/// two dates separated by "..". The dates are "YYYY-MM-DD", or "MM-DD" to match
+/// every year. Yearly ranges can wrap around the new year (eg: "12-24..01-01").
+pub fn within(v: &str, d: &Date) -> bool {
+    let (a, b) = v.split_once("..").unwrap_or((v, v));
+    let ((x, i), (y, j)) = match (yearly(a.trim(), d.year), yearly(b.trim(), d.year)) {
+        (Some(x), Some(y)) => (x, y),
+        _ => return false,
+    };
+    let (n, x, y) = (d.days(), x.days(), y.days());
+    if x <= y {
+        (x..=y).contains(&n)
+    } else {
+        // Only a yearly range can wrap.
+        i && j && (n >= x || n <= y)
+    }
+}
+/// Write the time 't' to 'w' using the format string 'f'.
+///
+/// The format supports these tokens, anything else is written as is:
+///
+/// - %H: Hour (00 - 23), or (1 - 12) if 'h24' is false.
+/// - %M: Minute (00 - 59).
+/// - %S: Second (00 - 59).
+/// - %p: AM or PM, nothing if 'h24' is true.
+/// - %A: Weekday name (Monday), %a: Short weekday name (Mon).
+/// - %d: Day of the month (01 - 31), %e: Day of the month (1 - 31).
+/// - %m: Month (01 - 12).
+/// - %B: Month name (January), %b: Short month name (Jan).
+/// - %Y: Year (2025), %y: Short year (25).
+/// - %%: A '%' sign.
+pub fn format(w: &mut impl Write, t: &Time, f: &str, h24: bool) -> fmt::Result {
+    let d = Date::from_time(t);
+    let (a, b) = (DAYS[d.weekday() as usize], MONTHS[(d.month as usize).clamp(1, 12) - 1]);
+    let mut i = f.chars();
+    while let Some(c) = i.next() {
+        if c != '%' {
+            w.write_char(c)?;
+            continue;
+        }
+        match i.next() {
+            Some('H') if h24 => write!(w, "{:02}", t.hours)?,
+            Some('H') => write!(w, "{}", if t.hours % 12 == 0 { 12 } else { t.hours % 12 })?,
+            Some('M') => write!(w, "{:02}", t.mins)?,
+            Some('S') => write!(w, "{:02}", t.secs)?,
+            Some('p') if h24 => (),
+            Some('p') => w.write_str(if t.hours < 12 { "AM" } else { "PM" })?,
+            Some('A') => w.write_str(a)?,
+            Some('a') => w.write_str(&a[0..3])?,
+            Some('d') => write!(w, "{:02}", d.day)?,
+            Some('e') => write!(w, "{}", d.day)?,
+            Some('m') => write!(w, "{:02}", d.month)?,
+            Some('B') => w.write_str(b)?,
+            Some('b') => w.write_str(&b[0..3])?,
+            Some('Y') => write!(w, "{}", d.year)?,
+            Some('y') => write!(w, "{:02}", d.year % 100)?,
+            Some('%') => w.write_char('%')?,
+            Some(x) => {
+                w.write_char('%')?;
+                w.write_char(x)?;
+            },
+            None => w.write_char('%')?,
+        }
+    }
+    Ok(())
+}
+
+/// Parse a "YYYY-MM-DD" or "MM-DD" date, using the year 'y' for the latter.
+/// Returns the Date and true if it was a "MM-DD" date.
+fn yearly(s: &str, y: u16) -> Option<(Date, bool)> {
+    if s.matches('-').count() != 1 {
+        return Some((Date::parse(s)?, false));
+    }
+    let (m, d) = s.split_once('-')?;
+    let (m, d) = (m.parse::<u8>().ok()?, d.parse::<u8>().ok()?);
+    if m == 0 || m > 12 || d == 0 || d > 31 {
+        return None;
+    }
+    Some((Date::new(y, m, d), true))
+}
@@ -0,0 +1,199 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::cmp::{Ord, min};
+use core::iter::Iterator;
+
+use inky_frame::frame::heaped::Static;
+use inky_frame::frame::{Color, Inky};
+
+use crate::art::isqrt;
+use crate::sky;
+
+/// Space (in pixels) left between the display edges and anything placed with
+/// a Position.
+pub const MARGIN: u16 = 8u16;
+
+pub enum Position {
+    TopLeft,
+    Top,
+    TopRight,
+    Center,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+pub trait Canvas {
+    fn width(&self) -> u16;
+    fn height(&self) -> u16;
+    fn pixel(&mut self, x: i32, y: i32, c: Color);
+
+    #[inline]
+    fn fill(&mut self, c: Color) {
+        let (w, h) = (self.width(), self.height());
+        self.rect(0, 0, w, h, c);
+    }
+    #[inline]
+    fn rect(&mut self, x: i32, y: i32, w: u16, h: u16, c: Color) {
+        // Clip to the screen here so large shapes don't waste time on the
+        // per-pixel bounds checks.
+        let (sx, sy) = (x.max(0), y.max(0));
+        let ex = min(x.saturating_add(w as i32), self.width() as i32);
+        let ey = min(y.saturating_add(h as i32), self.height() as i32);
+        for j in sy..ey {
+            for i in sx..ex {
+                self.pixel(i, j, c);
+            }
+        }
+    }
+    #[inline]
+    fn outline(&mut self, x: i32, y: i32, w: u16, h: u16, t: u16, c: Color) {
+        let t = min(t, min(w, h) / 2);
+        self.rect(x, y, w, t, c);
+        self.rect(x, y + h as i32 - t as i32, w, t, c);
+        self.rect(x, y, t, h, c);
+        self.rect(x + w as i32 - t as i32, y, t, h, c);
+    }
+}
+
+impl<const B: usize, const W: u16, const H: u16> Canvas for Inky<'_, B, W, H, Static<B>> {
+    #[inline(always)]
+    fn width(&self) -> u16 {
+        Inky::width(self)
+    }
+    #[inline(always)]
+    fn height(&self) -> u16 {
+        Inky::height(self)
+    }
+    #[inline(always)]
+    fn pixel(&mut self, x: i32, y: i32, c: Color) {
+        if x < 0 || y < 0 || x >= Inky::width(self) as i32 || y >= Inky::height(self) as i32 {
+            return;
+        }
+        self.set_pixel(x as u16, y as u16, c);
+    }
+}
+
+/// Returns the top left corner of a 'w' by 'h' box placed at the Position 'p',
+/// keeping 'MARGIN' pixels away from the edges.
+pub fn place(c: &impl Canvas, p: &Position, w: u16, h: u16) -> (i32, i32) {
+    let (dw, dh) = (c.width(), c.height());
+    let x = match p {
+        Position::TopLeft | Position::BottomLeft => MARGIN,
+        Position::TopRight | Position::BottomRight => dw.saturating_sub(w + MARGIN),
+        _ => dw.saturating_sub(w) / 2,
+    };
+    let y = match p {
+        Position::TopLeft | Position::Top | Position::TopRight => MARGIN,
+        Position::Center => dh.saturating_sub(h) / 2,
+        _ => dh.saturating_sub(h + MARGIN),
+    };
+    (x as i32, y as i32)
+}
+/// Draw a battery icon with it's top left corner at (x, y). The icon is 'w'
+/// wide and 'w / 2' tall, with the fill set by 'level' (0 - 100).
+pub fn battery(c: &mut impl Canvas, x: i32, y: i32, w: u16, level: u8) {
+    let h = w / 2;
+    let t = (w / 16).max(1);
+    let n = (w / 10).max(2);
+    // Body and the little nub on the right side.
+    c.rect(x, y, w, h, Color::White);
+    c.outline(x, y, w, h, t, Color::Black);
+    c.rect(x + w as i32, y + (h / 4) as i32, n, h / 2, Color::Black);
+    let v = match level {
+        0..=15 => Color::Red,
+        16..=40 => Color::Yellow,
+        _ => Color::Green,
+    };
+    let (iw, ih) = (w.saturating_sub(t * 4), h.saturating_sub(t * 4));
+    let f = ((iw as u32 * min(level, 100) as u32) / 100) as u16;
+    c.rect(x + (t * 2) as i32, y + (t * 2) as i32, f.max(t), ih, v);
+}
+/// Draw a lightning bolt (charging) icon with it's top left corner at (x, y).
+/// The icon is 's' wide and 's * 2' tall.
+pub fn charging(c: &mut impl Canvas, x: i32, y: i32, s: u16) {
+    let (w, h) = (s as i32, s as i32 * 2);
+    let p = [
+        (x + (w * 6) / 10, y),
+        (x, y + (h * 11) / 20),
+        (x + (w * 9) / 20, y + (h * 11) / 20),
+        (x + (w * 2) / 5, y + h),
+        (x + w, y + (h * 9) / 20),
+        (x + (w * 11) / 20, y + (h * 9) / 20),
+    ];
+    polygon(c, &p, Color::Yellow);
+}
+/// Draw the moon with it's top left corner at (x, y). The icon is 'r * 2' wide
+/// and tall, with the lit part set by the phase angle 'a' (in millidegrees).
+pub fn moon(c: &mut impl Canvas, x: i32, y: i32, r: u16, a: i32) {
+    let (r, k) = (r as i32, sky::cos(a));
+    let (cx, cy) = (x + r, y + r);
+    // The lit part grows from the right side and shrinks from the left.
+    let v = a.rem_euclid(360_000i32) < 180_000i32;
+    for j in -r..r {
+        // Sample from the center of the pixel row, in half pixels.
+        let h = j * 2 + 1;
+        let w = isqrt(r * r * 4 - h * h) / 2;
+        // The edge between the lit and dark parts is half of an ellipse.
+        let t = (w * k) / sky::ONE;
+        let (s, e) = if v { (t, w) } else { (-w, -t) };
+        c.rect(cx - w, cy + j, (w * 2) as u16, 1, Color::Black);
+        if e > s {
+            c.rect(cx + s, cy + j, (e - s) as u16, 1, Color::White);
+        }
+    }
+}
+/// Fill the polygon made by the points in 'p' using the even-odd rule.
+pub fn polygon(c: &mut impl Canvas, p: &[(i32, i32)], v: Color) {
+    if p.len() < 3 {
+        return;
+    }
+    let (mut t, mut b) = (i32::MAX, i32::MIN);
+    for (_, y) in p.iter() {
+        t = t.min(*y);
+        b = b.max(*y);
+    }
+    for j in t.max(0)..b.min(c.height() as i32) {
+        // Sample from the center of the pixel row so shared vertices don't
+        // get counted twice.
+        let y = j * 2 + 1;
+        let mut e = [0i32; 16];
+        let mut n = 0usize;
+        for k in 0..p.len() {
+            let ((x1, y1), (x2, y2)) = (p[k], p[(k + 1) % p.len()]);
+            let (y1, y2) = (y1 * 2, y2 * 2);
+            if ((y1 <= y && y2 > y) || (y2 <= y && y1 > y)) && n < e.len() {
+                e[n] = x1 + ((y - y1) * (x2 - x1)) / (y2 - y1);
+                n += 1;
+            }
+        }
+        e[0..n].sort_unstable();
+        for k in (0..n.saturating_sub(1)).step_by(2) {
+            for i in e[k]..e[k + 1] {
+                c.pixel(i, j, v);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::iter::Iterator;
+use core::ops::FnMut;
+use core::option::Option::{self, None, Some};
+use core::result::Result::{self, Err, Ok};
+
+use inky_frame::fs::{BlockDevice, DeviceError, Mode, Volume};
+
+/// Max length of a line read by 'line' and 'line_count'. Anything past this is
+/// ignored.
+const LINE_SIZE: usize = 256usize;
+
+/// Read the file at 'path' into the buffer 'buf', returning the amount of
+/// bytes read. If the file is larger than the buffer, only the start of the
+/// file is read.
+pub fn read<D: BlockDevice>(v: &Volume<'_, D>, path: &str, buf: &mut [u8]) -> Result<usize, DeviceError> {
+    let mut f = unsafe { v.file_open(path, Mode::READ)?.into_reader().unwrap_unchecked() };
+    // SAFETY: If opened with 'Mode::READ', 'into_reader' never fails.
+    let mut n = 0usize;
+    while n < buf.len() {
+        match f.read(&mut buf[n..])? {
+            0 => break,
+            c => n += c,
+        }
+    }
+    Ok(n)
+}
+/// Write the contents of 'buf' to the file at 'path', replacing any of it's
+/// existing contents. The file is created if it does not exist, but it's
+/// parent directory must exist.
+pub fn write<D: BlockDevice>(v: &Volume<'_, D>, path: &str, buf: &[u8]) -> Result<(), DeviceError> {
+    let mut f = unsafe { v.file_open(path, Mode::WRITE | Mode::CREATE | Mode::TRUNCATE)?.into_writer().unwrap_unchecked() };
+    // SAFETY: If opened with 'Mode::WRITE', 'into_writer' never fails.
+    let mut n = 0usize;
+    while n < buf.len() {
+        match f.write(&buf[n..])? {
+            // Nothing written means the card can't take any more, so don't
+            // keep trying forever.
+            0 => return Err(DeviceError::Write),
+            c => n += c,
+        }
+    }
+    f.flush()
+}
+/// Read line 'i' (starting at zero) of the file at 'path' into the buffer
+/// 'buf', returning the amount of bytes read, or None if the file does not
+/// have that many lines. Blank lines are skipped and not counted.
+///
+/// Unlike 'read', the file is read in chunks, so it can be any size.
+pub fn line<D: BlockDevice>(v: &Volume<'_, D>, path: &str, i: usize, buf: &mut [u8]) -> Result<Option<usize>, DeviceError> {
+    let (mut k, mut r) = (0usize, None);
+    lines(v, path, |x| {
+        if k < i {
+            k += 1;
+            return true;
+        }
+        let c = x.len().min(buf.len());
+        buf[0..c].copy_from_slice(&x[0..c]);
+        r = Some(c);
+        false
+    })?;
+    Ok(r)
+}
+/// Returns the number of lines in the file at 'path', not including blank
+/// lines.
+pub fn line_count<D: BlockDevice>(v: &Volume<'_, D>, path: &str) -> Result<usize, DeviceError> {
+    let mut n = 0usize;
+    lines(v, path, |_| {
+        n += 1;
+        true
+    })?;
+    Ok(n)
+}
+
+fn lines<D: BlockDevice>(v: &Volume<'_, D>, path: &str, mut f: impl FnMut(&[u8]) -> bool) -> Result<(), DeviceError> {
+    let mut r = unsafe { v.file_open(path, Mode::READ)?.into_reader().unwrap_unchecked() };
+    // SAFETY: If opened with 'Mode::READ', 'into_reader' never fails.
+    let (mut b, mut l, mut n) = ([0u8; 512], [0u8; LINE_SIZE], 0usize);
+    loop {
+        let c = r.read(&mut b)?;
+        if c == 0 {
+            break;
+        }
+        for x in b[0..c].iter() {
+            if *x != b'\n' {
+                if n < l.len() {
+                    l[n] = *x;
+                    n += 1;
+                }
+                continue;
+            }
+            let e = l[0..n].trim_ascii();
+            if !e.is_empty() && !f(e) {
+                return Ok(());
+            }
+            n = 0;
+        }
+    }
+    // Last line, without a newline at the end.
+    let e = l[0..n].trim_ascii();
+    if !e.is_empty() {
+        f(e);
+    }
+    Ok(())
+}
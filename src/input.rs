@@ -0,0 +1,158 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use inky_frame::InkyBoard;
+use inky_frame::hw::{Button, Buttons};
+
+/// Time (in milliseconds) between button reads when checking for gestures.
+const STEP: u32 = 10u32;
+
+pub const BUTTON_A: u8 = 0x01u8;
+pub const BUTTON_B: u8 = 0x02u8;
+pub const BUTTON_C: u8 = 0x04u8;
+pub const BUTTON_D: u8 = 0x08u8;
+pub const BUTTON_E: u8 = 0x10u8;
+
+pub enum Press {
+    Short,
+    Long,
+    Double,
+}
+
+/// Re-read the shift register and return a mask of the buttons that are
+/// currently held down.
+pub fn held(b: &mut Buttons) -> u8 {
+    if !b.read_pressed() {
+        return 0u8;
+    }
+    let mut v = 0u8;
+    if b.button_a() {
+        v |= BUTTON_A;
+    }
+    if b.button_b() {
+        v |= BUTTON_B;
+    }
+    if b.button_c() {
+        v |= BUTTON_C;
+    }
+    if b.button_d() {
+        v |= BUTTON_D;
+    }
+    if b.button_e() {
+        v |= BUTTON_E;
+    }
+    v
+}
+/// Returns the mask value of the Button, if it's one of the 'A' to 'E'
+/// buttons.
+#[inline]
+pub fn mask(v: &Button) -> u8 {
+    match v {
+        Button::ButtonA => BUTTON_A,
+        Button::ButtonB => BUTTON_B,
+        Button::ButtonC => BUTTON_C,
+        Button::ButtonD => BUTTON_D,
+        Button::ButtonE => BUTTON_E,
+        _ => 0u8,
+    }
+}
+/// Returns the mask of all the buttons held down together with the button(s)
+/// in the mask 'm', within 'wait' milliseconds of the first press.
+///
+/// If only the buttons in 'm' are pressed, the result will be equal to 'm'.
+pub fn chord(x: &InkyBoard<'_>, b: &mut Buttons, m: u8, wait: u32) -> u8 {
+    let (mut t, mut v) = (0u32, m);
+    while t < wait {
+        let h = held(b);
+        // Stop early once everything is released.
+        if h == 0 {
+            break;
+        }
+        v |= h;
+        x.sleep(STEP);
+        t += STEP;
+    }
+    if v != m {
+        // Wait for all the buttons to be released, so they don't count as
+        // another press.
+        release(x, b, v);
+    }
+    v
+}
+/// Determine the gesture of the button(s) in the mask 'm'.
+///
+/// This should be called right after the button press is detected. If the
+/// buttons are held longer than 'long' milliseconds, it's a long press. If the
+/// buttons are released and then pressed again within 'double' milliseconds,
+/// it's a double press.
+///
+/// If the board was woken up by the press, the time spent booting is not
+/// counted towards the long press time.
+pub fn gesture(x: &InkyBoard<'_>, b: &mut Buttons, m: u8, long: u32, double: u32) -> Press {
+    let mut t = 0u32;
+    while held(b) & m != 0 {
+        if t >= long {
+            // Wait for the release, so it doesn't get picked up as another
+            // press.
+            release(x, b, m);
+            return Press::Long;
+        }
+        x.sleep(STEP);
+        t += STEP;
+    }
+    t = 0u32;
+    while t < double {
+        x.sleep(STEP);
+        t += STEP;
+        if held(b) & m != 0 {
+            release(x, b, m);
+            return Press::Double;
+        }
+    }
+    Press::Short
+}
+/// Wait for the button(s) in the mask 'm' to be released, then wait up to
+/// 'wait' milliseconds for the next press. Returns the mask of the buttons
+/// pressed, once they're released, or zero if nothing was pressed in time.
+pub fn next(x: &InkyBoard<'_>, b: &mut Buttons, m: u8, wait: u32) -> u8 {
+    release(x, b, m);
+    let mut t = 0u32;
+    while t < wait {
+        let h = held(b);
+        if h != 0 {
+            release(x, b, h);
+            return h;
+        }
+        x.sleep(STEP);
+        t += STEP;
+    }
+    0u8
+}
+
+#[inline]
+pub fn release(x: &InkyBoard<'_>, b: &mut Buttons, m: u8) {
+    while held(b) & m != 0 {
+        x.sleep(STEP);
+    }
+}
@@ -0,0 +1,168 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::iter::Iterator;
+use core::option::Option::{self, None, Some};
+use core::result::Result::{self, Ok};
+
+use inky_frame::fs::{BlockDevice, DeviceError, Volume};
+
+use crate::files;
+
+/// Max size of a list file, in bytes.
+const SIZE: usize = 512usize;
+
+/// List of file names, one per line, stored in a file on the SD Card.
+///
+/// Names are compared without case, as FAT does.
+pub struct NameList {
+    buf: [u8; SIZE],
+    len: usize,
+}
+
+impl NameList {
+    #[inline(always)]
+    pub const fn new() -> NameList {
+        NameList { buf: [0u8; SIZE], len: 0usize }
+    }
+
+    /// Load the list from the file at 'path'. If the file does not exist,
+    /// the list is empty.
+    pub fn load<D: BlockDevice>(v: &Volume<'_, D>, path: &str) -> NameList {
+        let mut l = NameList::new();
+        l.len = files::read(v, path, &mut l.buf).unwrap_or(0);
+        l
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.names().next().is_none()
+    }
+    #[inline]
+    pub fn contains(&self, n: &[u8]) -> bool {
+        self.names().any(|v| v.eq_ignore_ascii_case(n))
+    }
+    /// Add the name to the list, if it's not already in it. Returns false if
+    /// the list is full.
+    #[inline]
+    pub fn add(&mut self, b: &[u8]) -> bool {
+        self.contains(b) || self.toggle(b).is_some()
+    }
+    /// Add the name if it's not in the list, or remove it if it is. Returns
+    /// Some(true) if it was added and Some(false) if it was removed.
+    ///
+    /// Returns None if the list is full.
+    pub fn toggle(&mut self, b: &[u8]) -> Option<bool> {
+        if self.contains(b) {
+            self.remove(b);
+            return Some(false);
+        }
+        // Add a newline before if the file didn't end with one.
+        let s = if self.len > 0 && self.buf[self.len - 1] != b'\n' { 1 } else { 0 };
+        if self.len + s + b.len() + 1 > SIZE {
+            return None;
+        }
+        if s > 0 {
+            self.buf[self.len] = b'\n';
+            self.len += 1;
+        }
+        self.buf[self.len..self.len + b.len()].copy_from_slice(b);
+        self.buf[self.len + b.len()] = b'\n';
+        self.len += b.len() + 1;
+        Some(true)
+    }
+    #[inline]
+    pub fn save<D: BlockDevice>(&self, v: &Volume<'_, D>, path: &str) -> Result<(), DeviceError> {
+        files::write(v, path, &self.buf[0..self.len])
+    }
+
+    #[inline]
+    fn names(&self) -> impl Iterator<Item = &[u8]> {
+        self.buf[0..self.len].split(|c| *c == b'\n').map(|v| v.trim_ascii()).filter(|v| !v.is_empty())
+    }
+    fn remove(&mut self, n: &[u8]) {
+        // Shift everything after each matching line over it.
+        let (mut r, mut w) = (0usize, 0usize);
+        while r < self.len {
+            let e = self.buf[r..self.len].iter().position(|c| *c == b'\n').map_or(self.len, |i| r + i + 1);
+            if !self.buf[r..e].trim_ascii().eq_ignore_ascii_case(n) {
+                self.buf.copy_within(r..e, w);
+                w += e - r;
+            }
+            r = e;
+        }
+        self.len = w;
+    }
+}
+
+/// Decides which files in a directory can be selected.
+pub struct Filter<'a> {
+    dir:  &'a str,
+    only: Option<&'a NameList>,
+    hide: &'a NameList,
+}
+
+impl<'a> Filter<'a> {
+    /// Create a Filter for the directory 'dir' that only allows names in the
+    /// list 'only', if it's not empty, and does not allow any paths in the
+    /// list 'hide'.
+    #[inline(always)]
+    pub fn new(dir: &'a str, only: Option<&'a NameList>, hide: &'a NameList) -> Filter<'a> {
+        Filter {
+            dir,
+            // An empty list would filter out everything, ignore it instead.
+            only: only.filter(|v| !v.is_empty()),
+            hide,
+        }
+    }
+
+    #[inline]
+    pub fn allows(&self, n: &str) -> bool {
+        if !self.only.is_none_or(|v| v.contains(n.as_bytes())) {
+            return false;
+        }
+        if self.hide.is_empty() {
+            return true;
+        }
+        let mut b = [0u8; 128];
+        match path(self.dir, n.as_bytes(), &mut b) {
+            Some(c) => !self.hide.contains(&b[0..c]),
+            None => true,
+        }
+    }
+}
+
+/// Join the directory 'dir' and name 'n' into the buffer 'buf', returning the
+/// length. Returns None if the buffer is too small.
+pub fn path(dir: &str, n: &[u8], buf: &mut [u8]) -> Option<usize> {
+    let (d, v) = (dir.trim_end_matches('/').as_bytes(), n);
+    let c = d.len() + v.len() + 1;
+    if c > buf.len() {
+        return None;
+    }
+    buf[0..d.len()].copy_from_slice(d);
+    buf[d.len()] = b'/';
+    buf[d.len() + 1..c].copy_from_slice(v);
+    Some(c)
+}
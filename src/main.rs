@@ -24,7 +24,21 @@
 extern crate core;
 extern crate rpsp;
 
+mod art;
+mod barcode;
+mod calendar;
+mod draw;
+mod files;
+mod input;
+mod list;
+mod pattern;
+mod power;
+mod qr;
 mod sideshow;
+mod sky;
+mod state;
+mod template;
+mod text;
 
 #[rpsp::entry]
 fn main() -> ! {
@@ -0,0 +1,222 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::cmp::Ord;
+use core::iter::Iterator;
+use core::ops::Fn;
+
+use inky_frame::frame::Color;
+
+use crate::art;
+use crate::draw::Canvas;
+
+/// Number of pattern types that can be drawn, including the generative art.
+pub const COUNT: usize = 5usize + art::COUNT;
+/// First pattern type that is generative art.
+pub const ART: usize = 5usize;
+
+pub const PALETTE: [Color; 7] = [
+    Color::Black,
+    Color::White,
+    Color::Green,
+    Color::Blue,
+    Color::Red,
+    Color::Yellow,
+    Color::Orange,
+];
+/// 4x4 ordered dither thresholds.
+const BAYER: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+// The panel only has 7 colors, so the in-between flag colors are dithered.
+const PURPLE: Shade = Shade::mix(Color::Red, Color::Blue, 8);
+const PINK: Shade = Shade::mix(Color::White, Color::Red, 6);
+const LIGHT_BLUE: Shade = Shade::mix(Color::White, Color::Blue, 6);
+
+/// Flags as their stripes from top to bottom, with the relative height of each
+/// stripe.
+const FLAGS: [&[(Shade, u8)]; 7] = [
+    // Rainbow
+    &[
+        (Shade::solid(Color::Red), 1),
+        (Shade::solid(Color::Orange), 1),
+        (Shade::solid(Color::Yellow), 1),
+        (Shade::solid(Color::Green), 1),
+        (Shade::solid(Color::Blue), 1),
+        (PURPLE, 1),
+    ],
+    // Transgender
+    &[(LIGHT_BLUE, 1), (PINK, 1), (Shade::solid(Color::White), 1), (PINK, 1), (LIGHT_BLUE, 1)],
+    // Bisexual
+    &[(Shade::mix(Color::Red, Color::Blue, 4), 2), (PURPLE, 1), (Shade::solid(Color::Blue), 2)],
+    // Pansexual
+    &[
+        (Shade::mix(Color::White, Color::Red, 10), 1),
+        (Shade::solid(Color::Yellow), 1),
+        (Shade::mix(Color::Blue, Color::Green, 8), 1),
+    ],
+    // Non-binary
+    &[
+        (Shade::solid(Color::Yellow), 1),
+        (Shade::solid(Color::White), 1),
+        (Shade::mix(Color::Red, Color::Blue, 10), 1),
+        (Shade::solid(Color::Black), 1),
+    ],
+    // Lesbian
+    &[
+        (Shade::mix(Color::Orange, Color::Red, 8), 1),
+        (Shade::solid(Color::Orange), 1),
+        (Shade::solid(Color::White), 1),
+        (PINK, 1),
+        (Shade::mix(Color::Red, Color::Blue, 4), 1),
+    ],
+    // Asexual
+    &[
+        (Shade::solid(Color::Black), 1),
+        (Shade::mix(Color::Black, Color::White, 8), 1),
+        (Shade::solid(Color::White), 1),
+        (PURPLE, 1),
+    ],
+];
+
+/// Color made by dithering 'level' out of every 16 pixels of 'b' over 'a'.
+pub struct Shade {
+    a:     Color,
+    b:     Color,
+    level: u8,
+}
+/// Small xorshift generator, so the same seed always draws the same pattern.
+pub struct Seed(u32);
+
+impl Seed {
+    #[inline(always)]
+    pub fn new(v: u32) -> Seed {
+        // Zero would only ever return zero.
+        Seed(if v == 0 { 0x9E37_79B9u32 } else { v })
+    }
+
+    /// Returns a number from zero up to (not including) 'n'.
+    #[inline]
+    pub fn next(&mut self, n: usize) -> usize {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x as usize % n.max(1)
+    }
+    /// Returns two different colors from the palette.
+    #[inline]
+    pub fn pair(&mut self) -> (Color, Color) {
+        let i = self.next(PALETTE.len());
+        (PALETTE[i], PALETTE[(i + 1 + self.next(PALETTE.len() - 1)) % PALETTE.len()])
+    }
+}
+impl Shade {
+    #[inline(always)]
+    pub const fn solid(c: Color) -> Shade {
+        Shade { a: c, b: c, level: 0u8 }
+    }
+    #[inline(always)]
+    pub const fn mix(a: Color, b: Color, level: u8) -> Shade {
+        Shade { a, b, level }
+    }
+
+    #[inline(always)]
+    pub fn at(&self, x: i32, y: i32) -> Color {
+        if self.level > BAYER[((y & 3) * 4 + (x & 3)) as usize] { self.b } else { self.a }
+    }
+}
+
+/// Draw the pattern type 'kind' (less than 'COUNT') over the whole Canvas. The
+/// colors and sizes are picked using 'seed'.
+///
+/// - 0: Solid color.
+/// - 1: Horizontal, vertical or diagonal stripes.
+/// - 2: Checkerboard.
+/// - 3: Gradient between two colors, dithered to the display colors.
+/// - 4: Pride flag.
+/// - 5 and up: Generative art, see 'art::draw'.
+pub fn draw(c: &mut impl Canvas, kind: usize, seed: u32) {
+    let mut r = Seed::new(seed);
+    let (w, h) = (c.width() as i32, c.height() as i32);
+    match kind {
+        0 => c.fill(PALETTE[r.next(PALETTE.len())]),
+        1 => {
+            let ((a, b), s, o) = (r.pair(), 8 + r.next(57) as i32, r.next(3));
+            each(c, |x, y| {
+                let k = match o {
+                    0 => y,
+                    1 => x,
+                    _ => x + y,
+                };
+                if (k / s) % 2 == 0 { a } else { b }
+            })
+        },
+        2 => {
+            let ((a, b), s) = (r.pair(), 16 + r.next(65) as i32);
+            each(c, |x, y| if (x / s + y / s) % 2 == 0 { a } else { b })
+        },
+        3 => {
+            let ((a, b), o) = (r.pair(), r.next(3));
+            let n = match o {
+                0 => h,
+                1 => w,
+                _ => w + h,
+            };
+            each(c, |x, y| {
+                let p = match o {
+                    0 => y,
+                    1 => x,
+                    _ => x + y,
+                };
+                Shade::mix(a, b, ((p * 17) / n).min(16) as u8).at(x, y)
+            })
+        },
+        4 => {
+            let f = FLAGS[r.next(FLAGS.len())];
+            let t = f.iter().map(|v| v.1 as i32).sum::<i32>();
+            let mut p = 0i32;
+            for (v, k) in f.iter() {
+                let (s, e) = ((h * p) / t, (h * (p + *k as i32)) / t);
+                for y in s..e {
+                    for x in 0..w {
+                        c.pixel(x, y, v.at(x, y));
+                    }
+                }
+                p += *k as i32;
+            }
+        },
+        _ => art::draw(c, kind - ART, &mut r),
+    }
+}
+
+#[inline]
+pub fn each(c: &mut impl Canvas, f: impl Fn(i32, i32) -> Color) {
+    let (w, h) = (c.width() as i32, c.height() as i32);
+    for y in 0..h {
+        for x in 0..w {
+            c.pixel(x, y, f(x, y));
+        }
+    }
+}
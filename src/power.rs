@@ -0,0 +1,156 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+extern crate rpsp;
+
+use core::arch::asm;
+use core::cmp::Ord;
+use core::iter::Iterator;
+use core::ptr::{read_volatile, write_volatile};
+
+use inky_frame::InkyBoard;
+use rpsp::adc::Adc;
+use rpsp::pin::{Pin, PinID};
+
+/// Number of ADC samples to average for a VSYS reading.
+const VSYS_SAMPLES: u32 = 8u32;
+
+// We use ALARM3 as ALARM0 is used by the 'rpsp' sleep and delay functions.
+const ALARM: u32 = 3u32;
+const ALARM_BIT: u32 = 1u32 << ALARM;
+
+const TIMER_BASE: usize = 0x4005_4000usize;
+const TIMER_ALARM: *mut u32 = (TIMER_BASE + 0x10 + (ALARM as usize * 4)) as *mut u32;
+const TIMER_ARMED: *mut u32 = (TIMER_BASE + 0x20) as *mut u32;
+const TIMER_RAWL: *mut u32 = (TIMER_BASE + 0x28) as *mut u32;
+const TIMER_INTR: *mut u32 = (TIMER_BASE + 0x34) as *mut u32;
+const TIMER_INTE_SET: *mut u32 = (TIMER_BASE + 0x2000 + 0x38) as *mut u32;
+const TIMER_INTE_CLR: *mut u32 = (TIMER_BASE + 0x3000 + 0x38) as *mut u32;
+
+// The PCF85063A INT output (open drain, active low) is wired to GPIO3, the
+// "I2C_INT" line on the InkyFrame. The falling edge is used, as the line stays
+// low until the alarm flag is cleared.
+const RTC_PIN: u32 = 3u32;
+const RTC_BIT: u32 = 1u32 << (RTC_PIN * 4 + 2);
+
+// GPIO 0 - 7 interrupt registers, each pin has 4 bits (level low, level high,
+// edge low, edge high).
+const IO_BASE: usize = 0x4001_4000usize;
+const IO_INTR: *mut u32 = (IO_BASE + 0xF0) as *mut u32;
+const IO_INTE_SET: *mut u32 = (IO_BASE + 0x2000 + 0x100) as *mut u32;
+const IO_INTE_CLR: *mut u32 = (IO_BASE + 0x3000 + 0x100) as *mut u32;
+const IO_IRQ_BIT: u32 = 1u32 << 13;
+
+const SCB_SCR: *mut u32 = 0xE000_ED10usize as *mut u32;
+const SCB_SCR_SEVONPEND: u32 = 1u32 << 4;
+const NVIC_ICPR: *mut u32 = 0xE000_E280usize as *mut u32;
+
+/// Read the VSYS voltage (in millivolts).
+///
+/// VSYS is connected to ADC3 (GPIO29) through a 1/3 voltage divider and the
+/// ADC uses the 3.3v rail as it's reference.
+pub fn vsys(b: &InkyBoard<'_>) -> u16 {
+    // On a Pico W, GPIO29 is also the CYW43 SPI clock. Keeping the CYW43 chip
+    // select (GPIO25) high makes it ignore the line while we sample it.
+    #[cfg(not(feature = "pico"))]
+    Pin::get(b, PinID::Pin25).into_output().high();
+    let mut a = Adc::new(b, PinID::Pin29);
+    let v = (0..VSYS_SAMPLES).map(|_| a.read() as u32).sum::<u32>() / VSYS_SAMPLES;
+    // 12-bit reading, 3.3v reference, 3x divider.
+    ((v * 9_900u32) >> 12) as u16
+}
+/// Put the core into sleep mode for 'us' microseconds, or until the PCF RTC
+/// alarm interrupt fires. Returns true if it was the RTC alarm.
+///
+/// Unlike the 'rpsp' sleep, this does not busy-loop. The timer alarm and GPIO
+/// interrupts are enabled in their peripherals, but NOT in the NVIC, and with
+/// 'SEVONPEND' set, the pending interrupt generates the event that wakes the
+/// core from 'WFE'. This means no interrupt handlers are needed.
+///
+/// The RTC alarm edge is latched by the GPIO block, so an alarm that happens
+/// while awake is seen on the next call.
+pub fn idle(us: u32) -> bool {
+    unsafe {
+        write_volatile(SCB_SCR, read_volatile(SCB_SCR) | SCB_SCR_SEVONPEND);
+        write_volatile(TIMER_INTE_SET, ALARM_BIT);
+        write_volatile(IO_INTE_SET, RTC_BIT);
+        // Writing the alarm value arms it.
+        write_volatile(TIMER_ALARM, read_volatile(TIMER_RAWL).wrapping_add(us.max(1)));
+        // Any event can wake us up, so loop until the alarm fires or the RTC
+        // line goes low.
+        while read_volatile(TIMER_ARMED) & ALARM_BIT != 0 && read_volatile(IO_INTR) & RTC_BIT == 0 {
+            asm!("wfe", options(nomem, nostack, preserves_flags));
+        }
+        let r = read_volatile(IO_INTR) & RTC_BIT != 0;
+        // Writing the armed bit disarms the alarm, if it's still waiting.
+        write_volatile(TIMER_ARMED, ALARM_BIT);
+        write_volatile(TIMER_INTE_CLR, ALARM_BIT);
+        write_volatile(IO_INTE_CLR, RTC_BIT);
+        // Clear the interrupts in the peripherals then in the NVIC, so the next
+        // wait triggers a new event.
+        write_volatile(TIMER_INTR, ALARM_BIT);
+        write_volatile(IO_INTR, RTC_BIT);
+        // TIMER_IRQ_n is IRQ 'n', so the bits match.
+        write_volatile(NVIC_ICPR, ALARM_BIT | IO_IRQ_BIT);
+        r
+    }
+}
+/// Forget any RTC alarm edge seen before now, so 'idle' only stops early for a
+/// new alarm.
+#[inline]
+pub fn idle_reset() {
+    unsafe { write_volatile(IO_INTR, RTC_BIT) };
+}
+/// Returns true if USB power (VBUS) is connected.
+///
+/// On a Pico, VBUS sense is on GPIO24 and is high when USB is connected,
+/// regardless of if a battery is also connected. The 'usb' voltage is not used.
+#[cfg(feature = "pico")]
+#[inline]
+pub fn vbus(b: &InkyBoard<'_>, _usb: u16) -> bool {
+    Pin::get(b, PinID::Pin24).into_input().is_high()
+}
+/// Returns true if USB power (VBUS) is connected.
+///
+/// On a Pico W (which the InkyFrame ships with), VBUS sense is on the CYW43
+/// WL_GPIO2 and GPIO24 is the CYW43 SPI data line, which can't be read without
+/// the wireless driver. Instead, VBUS feeds VSYS through a diode, so USB power
+/// is assumed when VSYS is at or above the 'usb' voltage (in millivolts). A
+/// LiPo cell never goes above 4.2v, while USB gives about 4.7v.
+#[cfg(not(feature = "pico"))]
+#[inline]
+pub fn vbus(b: &InkyBoard<'_>, usb: u16) -> bool {
+    vsys(b) >= usb
+}
+/// Convert a VSYS voltage (in millivolts) into a percentage (0 - 100) between
+/// the 'empty' and 'full' voltages.
+#[inline]
+pub fn level(v: u16, empty: u16, full: u16) -> u8 {
+    if v <= empty {
+        return 0;
+    }
+    if v >= full || full <= empty {
+        return 100;
+    }
+    (((v - empty) as u32 * 100u32) / (full - empty) as u32) as u8
+}
@@ -0,0 +1,522 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::cmp::Ord;
+use core::iter::{IntoIterator, Iterator};
+use core::option::Option::{self, None, Some};
+
+use inky_frame::frame::Color;
+
+use crate::draw::Canvas;
+
+/// Largest QR code version supported. Version 10 is 57x57 modules and can hold
+/// up to 271 bytes (with the Low error correction).
+const MAX_VERSION: u8 = 10u8;
+const MAX_SIZE: usize = 17usize + MAX_VERSION as usize * 4;
+/// Number of codewords (data and error correction) in the largest version.
+const MAX_CODEWORDS: usize = 368usize;
+const GRID: usize = (MAX_SIZE * MAX_SIZE + 7) / 8;
+
+// Tables below are indexed by the Ecc level then the version, starting at
+// version 1. Versions past 'MAX_VERSION' are not included.
+const ECC_CODEWORDS: [[u8; MAX_VERSION as usize]; 4] = [
+    [7, 10, 15, 20, 26, 18, 20, 24, 30, 18],
+    [10, 16, 26, 18, 24, 16, 18, 22, 22, 26],
+    [13, 22, 18, 26, 18, 24, 18, 22, 20, 24],
+    [17, 28, 22, 16, 22, 28, 26, 26, 24, 28],
+];
+const ECC_BLOCKS: [[u8; MAX_VERSION as usize]; 4] = [
+    [1, 1, 1, 1, 1, 2, 2, 2, 2, 4],
+    [1, 1, 1, 2, 2, 4, 4, 4, 5, 5],
+    [1, 1, 2, 2, 4, 4, 6, 6, 8, 8],
+    [1, 1, 2, 4, 4, 4, 5, 6, 8, 8],
+];
+
+// Penalty weights used when selecting the mask.
+const PENALTY_N1: u32 = 3u32;
+const PENALTY_N2: u32 = 3u32;
+const PENALTY_N3: u32 = 40u32;
+const PENALTY_N4: u32 = 10u32;
+
+/// QR code error correction level. Higher levels can be read when more of the
+/// code is damaged (or covered), but hold less data.
+pub enum Ecc {
+    Low,
+    Medium,
+    Quartile,
+    High,
+}
+
+/// QR code (version 1 to 10) encoded in byte mode.
+pub struct QrCode {
+    size: u8,
+    mods: [u8; GRID],
+    func: [u8; GRID],
+}
+
+impl Ecc {
+    #[inline(always)]
+    fn index(&self) -> usize {
+        match self {
+            Ecc::Low => 0,
+            Ecc::Medium => 1,
+            Ecc::Quartile => 2,
+            Ecc::High => 3,
+        }
+    }
+    #[inline(always)]
+    fn format_bits(&self) -> u32 {
+        match self {
+            Ecc::Low => 1,
+            Ecc::Medium => 0,
+            Ecc::Quartile => 3,
+            Ecc::High => 2,
+        }
+    }
+}
+impl QrCode {
+    /// Encode the bytes in 'data' as a QR code, using the smallest version
+    /// that fits. Returns None if the data is too large for a version 10 code
+    /// at the error correction level 'e'.
+    pub fn encode(data: &[u8], e: &Ecc) -> Option<QrCode> {
+        let (k, mut v) = (e.index(), 1u8);
+        loop {
+            // Byte mode uses a 16-bit length from version 10 up.
+            let n = 4 + if v < 10 { 8 } else { 16 } + data.len() * 8;
+            if n <= data_codewords(v, k) * 8 {
+                break;
+            }
+            if v >= MAX_VERSION {
+                return None;
+            }
+            v += 1;
+        }
+        let c = data_codewords(v, k);
+        let mut b = [0u8; MAX_CODEWORDS];
+        let mut w = Bits { buf: &mut b, pos: 0usize };
+        w.push(0x4, 4);
+        w.push(data.len() as u32, if v < 10 { 8 } else { 16 });
+        for i in data {
+            w.push(*i as u32, 8);
+        }
+        // Terminator, then pad to a full byte.
+        w.push(0, (c * 8 - w.pos).min(4) as u8);
+        w.push(0, ((8 - w.pos % 8) % 8) as u8);
+        let mut p = 0xECu32;
+        while w.pos < c * 8 {
+            w.push(p, 8);
+            p ^= 0xEC ^ 0x11;
+        }
+        let mut q = QrCode {
+            size: 17 + v * 4,
+            mods: [0u8; GRID],
+            func: [0u8; GRID],
+        };
+        q.draw_functions(v, e);
+        let mut o = [0u8; MAX_CODEWORDS];
+        let n = interleave(&b[0..c], v, k, &mut o);
+        q.draw_codewords(&o[0..n]);
+        // Select the mask with the lowest penalty. Masks are XOR'd, so applying
+        // it again removes it.
+        let (mut m, mut s) = (0u8, u32::MAX);
+        for i in 0..8u8 {
+            q.apply_mask(i);
+            q.draw_format(e, i);
+            let t = q.penalty();
+            if t < s {
+                (m, s) = (i, t);
+            }
+            q.apply_mask(i);
+        }
+        q.apply_mask(m);
+        q.draw_format(e, m);
+        Some(q)
+    }
+
+    /// Returns the width and height of the QR code, in modules.
+    #[inline(always)]
+    pub fn size(&self) -> u16 {
+        self.size as u16
+    }
+    /// Returns true if the module at (x, y) is dark.
+    #[inline]
+    pub fn get(&self, x: u16, y: u16) -> bool {
+        x < self.size as u16 && y < self.size as u16 && bit(&self.mods, self.index(x as i32, y as i32))
+    }
+    /// Draw the QR code with it's top left corner at (x, y), with each module
+    /// drawn as a 'scale' by 'scale' square. A light border 'quiet' modules
+    /// wide is drawn around the code.
+    pub fn draw(&self, c: &mut impl Canvas, x: i32, y: i32, scale: u16, quiet: u16) {
+        let (s, n) = (scale.max(1), self.size as u16);
+        c.rect(x, y, (n + quiet * 2) * s, (n + quiet * 2) * s, Color::White);
+        let (x, y) = (x + (quiet * s) as i32, y + (quiet * s) as i32);
+        for j in 0..n {
+            for i in 0..n {
+                if self.get(i, j) {
+                    c.rect(x + (i * s) as i32, y + (j * s) as i32, s, s, Color::Black);
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.size as usize + x as usize
+    }
+    #[inline(always)]
+    fn module(&self, x: i32, y: i32) -> bool {
+        bit(&self.mods, self.index(x, y))
+    }
+    #[inline]
+    fn set(&mut self, x: i32, y: i32, v: bool) {
+        let i = self.index(x, y);
+        if v {
+            self.mods[i / 8] |= 1 << (i % 8);
+        } else {
+            self.mods[i / 8] &= !(1 << (i % 8));
+        }
+    }
+    #[inline]
+    fn set_func(&mut self, x: i32, y: i32, v: bool) {
+        let i = self.index(x, y);
+        self.func[i / 8] |= 1 << (i % 8);
+        self.set(x, y, v);
+    }
+    fn penalty(&self) -> u32 {
+        let n = self.size as i32;
+        let mut r = 0u32;
+        // Runs of the same color and finder-like patterns, in the rows (k = 0)
+        // then the columns (k = 1).
+        for k in 0..2 {
+            for j in 0..n {
+                let (mut c, mut l, mut h) = (false, 0i32, [0i32; 7]);
+                for i in 0..n {
+                    let v = if k == 0 { self.module(i, j) } else { self.module(j, i) };
+                    if v == c {
+                        l += 1;
+                        if l == 5 {
+                            r += PENALTY_N1;
+                        } else if l > 5 {
+                            r += 1;
+                        }
+                    } else {
+                        history(&mut h, l, n);
+                        if !c {
+                            r += finders(&h) * PENALTY_N3;
+                        }
+                        (c, l) = (v, 1);
+                    }
+                }
+                // Treat the border as light modules.
+                if c {
+                    history(&mut h, l, n);
+                    l = 0;
+                }
+                history(&mut h, l + n, n);
+                r += finders(&h) * PENALTY_N3;
+            }
+        }
+        // 2x2 blocks of the same color.
+        for y in 0..n - 1 {
+            for x in 0..n - 1 {
+                let v = self.module(x, y);
+                if v == self.module(x + 1, y) && v == self.module(x, y + 1) && v == self.module(x + 1, y + 1) {
+                    r += PENALTY_N2;
+                }
+            }
+        }
+        // Balance of dark and light modules.
+        let t = n * n;
+        let d = (0..t as usize).filter(|i| bit(&self.mods, *i)).count() as i32;
+        let k = ((d * 20 - t * 10).abs() + t - 1) / t - 1;
+        r + k as u32 * PENALTY_N4
+    }
+    fn apply_mask(&mut self, m: u8) {
+        let n = self.size as i32;
+        for y in 0..n {
+            for x in 0..n {
+                let v = match m {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let i = self.index(x, y);
+                if v && !bit(&self.func, i) {
+                    self.mods[i / 8] ^= 1 << (i % 8);
+                }
+            }
+        }
+    }
+    fn draw_format(&mut self, e: &Ecc, m: u8) {
+        let d = e.format_bits() << 3 | m as u32;
+        let mut r = d;
+        for _ in 0..10 {
+            r = (r << 1) ^ ((r >> 9) * 0x537);
+        }
+        let b = (d << 10 | r) ^ 0x5412;
+        let f = |i: i32| (b >> i) & 1 != 0;
+        let n = self.size as i32;
+        // First copy, around the top left finder.
+        for i in 0..6 {
+            self.set_func(8, i, f(i));
+        }
+        self.set_func(8, 7, f(6));
+        self.set_func(8, 8, f(7));
+        self.set_func(7, 8, f(8));
+        for i in 9..15 {
+            self.set_func(14 - i, 8, f(i));
+        }
+        // Second copy, split between the other two finders.
+        for i in 0..8 {
+            self.set_func(n - 1 - i, 8, f(i));
+        }
+        for i in 8..15 {
+            self.set_func(8, n - 15 + i, f(i));
+        }
+        // Always dark.
+        self.set_func(8, n - 8, true);
+    }
+    fn draw_finder(&mut self, x: i32, y: i32) {
+        let n = self.size as i32;
+        for j in -4..=4i32 {
+            for i in -4..=4i32 {
+                let (a, b) = (x + i, y + j);
+                if a < 0 || b < 0 || a >= n || b >= n {
+                    continue;
+                }
+                let d = i.abs().max(j.abs());
+                self.set_func(a, b, d != 2 && d != 4);
+            }
+        }
+    }
+    fn draw_codewords(&mut self, d: &[u8]) {
+        let n = self.size as i32;
+        let (mut i, mut r) = (0usize, n - 1);
+        // Zig-zag through the two module wide columns, from the right, skipping
+        // the vertical timing pattern.
+        while r >= 1 {
+            if r == 6 {
+                r = 5;
+            }
+            let u = (r + 1) & 2 == 0;
+            for v in 0..n {
+                for j in 0..2 {
+                    let (x, y) = (r - j, if u { n - 1 - v } else { v });
+                    if bit(&self.func, self.index(x, y)) || i >= d.len() * 8 {
+                        continue;
+                    }
+                    self.set(x, y, (d[i / 8] >> (7 - i % 8)) & 1 != 0);
+                    i += 1;
+                }
+            }
+            r -= 2;
+        }
+    }
+    fn draw_functions(&mut self, v: u8, e: &Ecc) {
+        let n = self.size as i32;
+        for i in 0..n {
+            self.set_func(6, i, i % 2 == 0);
+            self.set_func(i, 6, i % 2 == 0);
+        }
+        self.draw_finder(3, 3);
+        self.draw_finder(n - 4, 3);
+        self.draw_finder(3, n - 4);
+        // Alignment patterns, skipping the ones on top of the finders.
+        let (p, c) = alignment(v);
+        for a in 0..c {
+            for b in 0..c {
+                if (a == 0 && b == 0) || (a == 0 && b == c - 1) || (a == c - 1 && b == 0) {
+                    continue;
+                }
+                for j in -2..=2i32 {
+                    for i in -2..=2i32 {
+                        self.set_func(p[a] + i, p[b] + j, i.abs().max(j.abs()) != 1);
+                    }
+                }
+            }
+        }
+        // Mark the format bits as function modules, the actual values are set
+        // once the mask is selected.
+        self.draw_format(e, 0);
+        if v < 7 {
+            return;
+        }
+        let mut r = v as u32;
+        for _ in 0..12 {
+            r = (r << 1) ^ ((r >> 11) * 0x1F25);
+        }
+        let b = (v as u32) << 12 | r;
+        for i in 0..18 {
+            let (x, y, f) = (n - 11 + i % 3, i / 3, (b >> i) & 1 != 0);
+            self.set_func(x, y, f);
+            self.set_func(y, x, f);
+        }
+    }
+}
+
+struct Bits<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl Bits<'_> {
+    #[inline]
+    fn push(&mut self, v: u32, n: u8) {
+        for i in (0..n).rev() {
+            if (v >> i) & 1 != 0 {
+                self.buf[self.pos / 8] |= 0x80 >> (self.pos % 8);
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+#[inline(always)]
+fn bit(b: &[u8], i: usize) -> bool {
+    (b[i / 8] >> (i % 8)) & 1 != 0
+}
+/// Returns the number of data and error correction codewords in the version.
+#[inline]
+fn codewords(v: u8) -> usize {
+    let v = v as usize;
+    let mut r = (16 * v + 128) * v + 64;
+    if v >= 2 {
+        let a = v / 7 + 2;
+        r -= (25 * a - 10) * a - 55;
+        if v >= 7 {
+            r -= 36;
+        }
+    }
+    r / 8
+}
+#[inline]
+fn data_codewords(v: u8, k: usize) -> usize {
+    let i = v as usize - 1;
+    codewords(v) - ECC_CODEWORDS[k][i] as usize * ECC_BLOCKS[k][i] as usize
+}
+/// Returns the alignment pattern center positions and how many there are.
+fn alignment(v: u8) -> ([i32; 3], usize) {
+    let mut p = [0i32; 3];
+    if v == 1 {
+        return (p, 0);
+    }
+    let (c, n) = (v as i32 / 7 + 2, 17 + v as i32 * 4);
+    let s = (v as i32 * 8 + c * 3 + 5) / (c * 4 - 4) * 2;
+    p[0] = 6;
+    for i in 1..c {
+        p[i as usize] = n - 7 - (c - 1 - i) * s;
+    }
+    (p, c as usize)
+}
+/// Split the data codewords into blocks, add the error correction codewords to
+/// each and interleave them into 'out'. Returns the number of codewords.
+fn interleave(d: &[u8], v: u8, k: usize, out: &mut [u8]) -> usize {
+    let (b, e) = (ECC_BLOCKS[k][v as usize - 1] as usize, ECC_CODEWORDS[k][v as usize - 1] as usize);
+    let t = codewords(v);
+    // The first 's' blocks are one codeword shorter than the rest.
+    let (s, l) = (b - t % b, t / b - e);
+    let mut g = [0u8; 30];
+    divisor(&mut g[0..e]);
+    let mut x = [0u8; MAX_CODEWORDS];
+    let mut o = 0usize;
+    for j in 0..b {
+        let a = j * l + j.saturating_sub(s);
+        let n = l + if j < s { 0 } else { 1 };
+        remainder(&d[a..a + n], &g[0..e], &mut x[j * e..(j + 1) * e]);
+    }
+    for i in 0..=l {
+        for j in 0..b {
+            if i == l && j < s {
+                continue;
+            }
+            out[o] = d[j * l + j.saturating_sub(s) + i];
+            o += 1;
+        }
+    }
+    for i in 0..e {
+        for j in 0..b {
+            out[o] = x[j * e + i];
+            o += 1;
+        }
+    }
+    o
+}
+/// Reed-Solomon generator polynomial for 'r.len()' error correction codewords.
+fn divisor(r: &mut [u8]) {
+    let n = r.len();
+    r.fill(0);
+    r[n - 1] = 1;
+    let mut v = 1u8;
+    for _ in 0..n {
+        for j in 0..n {
+            r[j] = multiply(r[j], v);
+            if j + 1 < n {
+                r[j] ^= r[j + 1];
+            }
+        }
+        v = multiply(v, 0x02);
+    }
+}
+fn remainder(d: &[u8], g: &[u8], r: &mut [u8]) {
+    r.fill(0);
+    for b in d {
+        let f = *b ^ r[0];
+        r.copy_within(1.., 0);
+        r[r.len() - 1] = 0;
+        for (i, v) in g.into_iter().enumerate() {
+            r[i] ^= multiply(*v, f);
+        }
+    }
+}
+/// Multiply two values in GF(2^8) with the QR code polynomial (0x11D).
+#[inline]
+fn multiply(x: u8, y: u8) -> u8 {
+    let mut z = 0u32;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    z as u8
+}
+/// Add a run length to the finder pattern history, newest first.
+#[inline]
+fn history(h: &mut [i32; 7], l: i32, n: i32) {
+    // The first run also counts the light border before it.
+    let l = if h[0] == 0 { l + n } else { l };
+    h.copy_within(0..6, 1);
+    h[0] = l;
+}
+/// Returns the number of finder-like (1:1:3:1:1) patterns at the end of the
+/// history, with light space on either side.
+#[inline]
+fn finders(h: &[i32; 7]) -> u32 {
+    let n = h[1];
+    let c = n > 0 && h[2] == n && h[3] == n * 3 && h[4] == n && h[5] == n;
+    (c && h[0] >= n * 4 && h[6] >= n) as u32 + (c && h[6] >= n * 4 && h[0] >= n) as u32
+}
@@ -23,30 +23,45 @@ extern crate core;
 extern crate inky_frame;
 extern crate rpsp;
 
+use core::cmp::Ord;
 use core::convert::{From, Into};
+use core::fmt::Write;
 use core::iter::{IntoIterator, Iterator};
-use core::option::Option::{None, Some};
-use core::result::Result::{self, Ok};
-use core::unreachable;
+use core::option::Option::{self, None, Some};
+use core::result::Result::{self, Err, Ok};
+use core::{matches, unreachable, write};
 
 use inky_frame::InkyBoard;
 use inky_frame::frame::heaped::Static;
 use inky_frame::frame::tga::{ImageError, TgaParser};
-use inky_frame::frame::{Inky, InkyPins, InkyRotation};
+use inky_frame::frame::{Color, Inky, InkyPins, InkyRotation};
 use inky_frame::fs::{BlockDevice, DeviceError, Mode, Volume};
 use inky_frame::hw::{Button, Buttons, Leds};
 use rpsp::MayFail;
 use rpsp::rand::Rand;
 
-use crate::out;
+use crate::barcode::{Barcode, Symbology};
+use crate::calendar::{self, Date};
+use crate::draw::{self, Canvas, Position};
+use crate::input::{self, Press};
+use crate::list::{self, Filter, NameList};
+use crate::qr::{Ecc, QrCode};
+use crate::sky::{self, Moon, Sun};
+use crate::state::State;
+use crate::text::{self, Align, Font, TextBuf};
+use crate::{files, out, pattern, power, template};
 
 // =================== [   Configuration   ] ===================
-/// Time in milliseconds) to wait for a button check. Only
-/// takes affect when NOT on battery power.
+/// Time (in milliseconds) to wait between button checks. Only
+/// takes affect when NOT on battery power. The device sleeps
+/// between the checks.
 const SLEEP_STEP: u32 = 50u32;
 /// Time (in seconds) to wake up and change the current
 /// badge and/or background.
 const SLEEP_TIME: u32 = 15u32 * 60u32;
+/// Time (in seconds) to wake up and change the current
+/// badge and/or background when on USB power.
+const SLEEP_TIME_USB: u32 = 5u32 * 60u32;
 
 /// Directory name in the SD Card root to get the badge
 /// images from.
@@ -54,6 +69,245 @@ const DIR_BADGES: &str = "/badges";
 /// Directory name in the SD Card root to get the background
 /// images from.
 const DIR_BACKGROUNDS: &str = "/backgrounds";
+/// Where to get the background images from.
+///
+/// - Backgrounds::Files: The images in 'DIR_BACKGROUNDS'. If
+///   there are none, generated backgrounds are used.
+/// - Backgrounds::Generated: Only generated backgrounds.
+/// - Backgrounds::Both: Both the images and the generated
+///   backgrounds.
+/// - Backgrounds::Art: Only the generative art backgrounds.
+const BACKGROUNDS: Backgrounds = Backgrounds::Files;
+/// Directory name in the SD Card root to get the caption
+/// sidecar files from. The sidecar for "/badges/hello.tga"
+/// is "/captions/hello.txt".
+const DIR_CAPTIONS: &str = "/captions";
+/// Directory name in the SD Card root to get the date images
+/// from. An image named "YYYY-MM-DD.tga" is shown on that day
+/// and one named "MM-DD.tga" is shown on that day every year.
+const DIR_DATES: &str = "/dates";
+/// File on the SD Card that maps dates to images, one per line
+/// as "dates|path" (eg: "2026-06-01..2026-06-03|/dates/conf.tga").
+/// The dates are a single date or a range, and can use the
+/// "MM-DD" format to match every year.
+const FILE_DATES: &str = "/dates/dates.txt";
+/// Show the image for the current date, if there is one, in
+/// place of the Badge on wake-ups.
+const DATES: bool = false;
+
+/// File on the SD Card used to keep settings between power
+/// cycles. It's parent directory must exist.
+const FILE_STATE: &str = "/system/state";
+/// File on the SD Card with the names of the favorite badges,
+/// one per line. It's parent directory must exist.
+const FILE_FAVORITES: &str = "/system/favorites";
+/// File on the SD Card with the paths of the hidden badges and
+/// backgrounds, one per line. It's parent directory must exist.
+const FILE_HIDDEN: &str = "/system/hidden";
+/// Image to display when the Cover Action is used. If it can't
+/// be loaded, the display is filled with 'COVER_COLOR'.
+const FILE_COVER: &str = "/system/cover.tga";
+/// Color to fill the display with when the Cover Action is used
+/// and the cover image can't be loaded.
+const COVER_COLOR: Color = Color::White;
+/// File on the SD Card with the status board entries, one
+/// per line. An icon image can be added to an entry by
+/// putting it's path after a '|' (eg: "Busy|/icons/busy.tga").
+/// Icons are drawn at (0, 0) before the text.
+const FILE_STATUS: &str = "/system/status.txt";
+/// PSF2 font file used to draw all text. If it can't be loaded,
+/// the built-in 5x7 font is used, which only supports ASCII.
+const FILE_FONT: &str = "/system/font.psf";
+/// Image to display when the battery is low. If it can't be
+/// loaded, a built-in battery screen is drawn instead.
+const FILE_LOWBAT: &str = "/system/lowbat.tga";
+
+/// Latitude and longitude (in millidegrees, with North and
+/// East positive) used to work out the sunrise and sunset
+/// times, or None to disable the night directories (eg:
+/// Some((51_507, -128)) for London).
+const LOCATION: Option<(i32, i32)> = None;
+/// Offset (in minutes) of the RTC time from UTC (eg: -300 for
+/// New York). This needs to be changed for daylight savings.
+const TIMEZONE: i16 = 0i16;
+/// Directory name in the SD Card root to get the badge images
+/// from between sunset and sunrise. If it's missing or empty,
+/// 'DIR_BADGES' is used.
+const DIR_BADGES_NIGHT: &str = "/night/badges";
+/// Directory name in the SD Card root to get the background
+/// images from between sunset and sunrise. If it's missing or
+/// empty, 'DIR_BACKGROUNDS' is used.
+const DIR_BACKGROUNDS_NIGHT: &str = "/night/backgrounds";
+
+/// Use template badges instead of the images in 'DIR_BADGES'.
+/// Each line of 'FILE_TEMPLATE_FIELDS' (after the header) is
+/// drawn as a badge, using 'FILE_TEMPLATE_LAYOUT'.
+const TEMPLATE: bool = false;
+/// Image drawn behind the template fields. If it can't be
+/// loaded, the fields are drawn directly on the background.
+const FILE_TEMPLATE_IMAGE: &str = "/template/badge.tga";
+/// CSV file with the template fields. The first line is the
+/// names of the fields (eg: "name,pronouns,role,company").
+const FILE_TEMPLATE_FIELDS: &str = "/template/fields.csv";
+/// File that describes where to draw each template field, one
+/// per line as "name x y width height [scale] [color] [align]"
+/// (eg: "name 40 120 560 80 6 black center").
+const FILE_TEMPLATE_LAYOUT: &str = "/template/layout.txt";
+
+/// Battery voltage (in millivolts) that is considered empty.
+const BATTERY_EMPTY: u16 = 3_000u16;
+/// Battery voltage (in millivolts) that is considered full.
+const BATTERY_FULL: u16 = 4_200u16;
+/// Battery voltage (in millivolts) to show the low battery
+/// screen at. Automatic wake-ups stop until a button is
+/// pressed when under this value.
+const BATTERY_LOW: u16 = 3_300u16;
+/// Draw a battery level icon in the top right corner of the
+/// display on each update. A charging icon is also drawn
+/// next to it when on USB power.
+const BATTERY_ICON: bool = false;
+/// VSYS voltage (in millivolts) that means USB power is
+/// connected. Only used on a Pico W, which can't read VBUS
+/// directly. Raise it above 5_500 if the battery supply is
+/// higher than a LiPo cell (like 3 AA cells or a booster).
+const BATTERY_USB: u16 = 4_500u16;
+
+/// Color of the status board text.
+const STATUS_COLOR: Color = Color::Black;
+/// Color of the box drawn behind the status board text, or
+/// None to draw the text directly on the background.
+const STATUS_BOX: Option<Color> = Some(Color::White);
+/// Largest scale of the status board text. The text will be
+/// made smaller if it does not fit.
+const STATUS_SCALE: u16 = 8u16;
+
+/// File on the SD Card with the countdown events, one per line
+/// as "date|name|after" (eg: "2025-12-31 23:59|New Year"). The
+/// date is "YYYY-MM-DD" with an optional "HH:MM" time. The
+/// 'after' value is optional and is an image path (starting
+/// with '/') or a message to show once the event has passed.
+///
+/// The countdown uses the status board text settings.
+const FILE_EVENTS: &str = "/system/events.txt";
+/// Message shown once an event has passed if it does not have
+/// it's own. Any '{event}' is replaced with the event name.
+const COUNTDOWN_AFTER: &str = "{event} is here!";
+/// Time (in seconds) to keep showing an event after it has
+/// passed, before moving on to the next one.
+const COUNTDOWN_KEEP: u32 = 24u32 * 60u32 * 60u32;
+
+/// Caption to draw over the badge image.
+///
+/// - Caption::None: No caption.
+/// - Caption::Name: The badge file name, without the extension.
+/// - Caption::Sidecar: The text in the badge's sidecar file,
+///   in 'DIR_CAPTIONS'.
+/// - Caption::Template(text): The text with any '{name}',
+///   '{caption}', '{date}' and '{time}' tokens replaced.
+const CAPTION: Caption = Caption::None;
+/// Where on the display to draw the caption.
+const CAPTION_POSITION: Position = Position::Bottom;
+/// Color of the caption text.
+const CAPTION_COLOR: Color = Color::Black;
+/// Color of the box drawn behind the caption text, or None to
+/// draw the text directly on the badge.
+const CAPTION_BOX: Option<Color> = Some(Color::White);
+/// Scale of the caption text.
+const CAPTION_SCALE: u16 = 2u16;
+
+/// Draw a clock over the display on each update, using the
+/// PCF RTC time. The time shown is when the display was last
+/// updated.
+const CLOCK: bool = false;
+/// Format of the clock text. Supports %H (hour), %M (minute),
+/// %S (second), %p (AM/PM), %A/%a (weekday), %d/%e (day),
+/// %m/%B/%b (month) and %Y/%y (year). New lines are allowed.
+const CLOCK_FORMAT: &str = "%H:%M\n%a %e %b %Y";
+/// Use a 24-hour clock for '%H'. If false, a 12-hour clock is
+/// used and '%p' is AM or PM.
+const CLOCK_24H: bool = true;
+/// Where on the display to draw the clock.
+const CLOCK_POSITION: Position = Position::TopLeft;
+/// Color of the clock text.
+const CLOCK_COLOR: Color = Color::Black;
+/// Color of the box drawn behind the clock text, or None to
+/// draw the text directly on the badge.
+const CLOCK_BOX: Option<Color> = Some(Color::White);
+/// Scale of the clock text.
+const CLOCK_SCALE: u16 = 3u16;
+/// Small "last updated" stamp to draw on each update, using the
+/// same format as 'CLOCK_FORMAT', or None to not draw it. This
+/// works with or without the clock.
+const CLOCK_STAMP: Option<&str> = None;
+/// Where on the display to draw the "last updated" stamp.
+const CLOCK_STAMP_POSITION: Position = Position::BottomRight;
+
+/// Draw the moon phase over the display on each update. The
+/// Moon is worked out from the RTC date, using 'TIMEZONE'.
+const MOON: bool = false;
+/// Where on the display to draw the moon phase.
+const MOON_POSITION: Position = Position::BottomLeft;
+/// Size (in pixels) of the moon phase icon.
+const MOON_SIZE: u16 = 48u16;
+/// Color of the moon phase text.
+const MOON_COLOR: Color = Color::Black;
+/// Color of the box drawn behind the moon phase, or None to
+/// draw it directly on the badge.
+const MOON_BOX: Option<Color> = Some(Color::White);
+/// Scale of the moon phase text.
+const MOON_SCALE: u16 = 2u16;
+/// Add the day length to the moon phase text. This only works
+/// when 'LOCATION' is set.
+const MOON_DAY_LENGTH: bool = true;
+
+/// File on the SD Card with the text to show as a QR code
+/// when the ShowQr Action is used (eg: a URL, vCard or
+/// fediverse handle). Up to 271 bytes, depending on 'QR_ECC'.
+const FILE_QR: &str = "/system/qr.txt";
+/// Error correction level of the QR code. Higher levels can
+/// be read when partly covered, but hold less text.
+const QR_ECC: Ecc = Ecc::Medium;
+/// Size (in pixels) of each QR code module, or zero to use the
+/// largest size that fits on the display.
+const QR_SCALE: u16 = 0u16;
+/// Width (in modules) of the light border around the QR code.
+/// Most readers need at least 4.
+const QR_QUIET: u16 = 4u16;
+/// Where on the display to draw the QR code.
+const QR_POSITION: Position = Position::Center;
+/// Draw the QR code over the current background instead of a
+/// blank display.
+const QR_BACKGROUND: bool = false;
+
+/// Text to encode in the barcode. If None, the text is read
+/// from the badge's sidecar file in 'DIR_CAPTIONS' (eg:
+/// "/captions/hello.code") for the overlay, or 'FILE_BARCODE'
+/// for the ShowBarcode Action.
+const BARCODE_TEXT: Option<&str> = None;
+/// File on the SD Card with the text to show as a barcode when
+/// the ShowBarcode Action is used and 'BARCODE_TEXT' is None.
+const FILE_BARCODE: &str = "/system/barcode.txt";
+/// Type of barcode to draw.
+///
+/// - Symbology::Code128: Any printable ASCII text.
+/// - Symbology::Ean13: 12 or 13 digits.
+const BARCODE_TYPE: Symbology = Symbology::Code128;
+/// Draw the barcode over every badge. Badges without any text
+/// to encode do not get a barcode.
+const BARCODE_OVERLAY: bool = false;
+/// Where on the display to draw the barcode overlay. The
+/// ShowBarcode Action always draws it in the center.
+const BARCODE_POSITION: Position = Position::Top;
+/// Width (in pixels) of the narrowest bar, or zero to use the
+/// largest width that fits on the display.
+const BARCODE_SCALE: u16 = 0u16;
+/// Height (in pixels) of the bars.
+const BARCODE_HEIGHT: u16 = 64u16;
+/// Width (in modules) of the light border on both sides of the
+/// barcode. Most scanners need at least 10.
+const BARCODE_QUIET: u16 = 10u16;
+/// Draw the encoded text under the barcode.
+const BARCODE_LABEL: bool = true;
 
 /// Action to return when the 'A' button is pressed.
 const BUTTON_A: Action = Action::None;
@@ -65,8 +319,75 @@ const BUTTON_C: Action = Action::Rand;
 const BUTTON_D: Action = Action::Prev;
 /// Action to return when the 'E' button is pressed.
 const BUTTON_E: Action = Action::Next;
+
+/// Action to return when the 'A' button is held down.
+const BUTTON_A_LONG: Action = Action::None;
+/// Action to return when the 'B' button is held down.
+const BUTTON_B_LONG: Action = Action::None;
+/// Action to return when the 'C' button is held down.
+const BUTTON_C_LONG: Action = Action::None;
+/// Action to return when the 'D' button is held down.
+const BUTTON_D_LONG: Action = Action::None;
+/// Action to return when the 'E' button is held down.
+const BUTTON_E_LONG: Action = Action::None;
+
+/// Action to return when the 'A' button is double pressed.
+const BUTTON_A_DOUBLE: Action = Action::None;
+/// Action to return when the 'B' button is double pressed.
+const BUTTON_B_DOUBLE: Action = Action::None;
+/// Action to return when the 'C' button is double pressed.
+const BUTTON_C_DOUBLE: Action = Action::None;
+/// Action to return when the 'D' button is double pressed.
+const BUTTON_D_DOUBLE: Action = Action::None;
+/// Action to return when the 'E' button is double pressed.
+const BUTTON_E_DOUBLE: Action = Action::None;
+
+/// Actions to return when multiple buttons are held down at
+/// the same time. Each entry is the mask of the buttons, made
+/// by combining the 'BTN_*' values, and the Action.
+///
+/// Chords take priority over the single button Actions. The
+/// size of the list must match the number of entries (eg:
+/// '[(u8, Action); 1] = [(BTN_A | BTN_E, Action::Reset)]').
+const CHORDS: [(u8, Action); 0] = [];
+/// Time (in milliseconds) to wait for the other buttons of a
+/// chord after the first one is pressed.
+const CHORD_WAIT: u32 = 150u32;
+
+/// Make the Lock Action a secure lock. Once locked, all the
+/// buttons are ignored until the 'LOCK_PIN' sequence is
+/// entered, which unlocks it.
+const LOCK_SECURE: bool = false;
+/// Button sequence to unlock the secure lock, made from the
+/// 'BTN_*' values. Only single buttons are supported.
+const LOCK_PIN: [u8; 4] = [BTN_D, BTN_D, BTN_E, BTN_C];
+/// Time (in milliseconds) to wait for the next button of the
+/// 'LOCK_PIN' sequence before the attempt is dropped.
+const LOCK_WAIT: u32 = 5_000u32;
+
+/// When the Lock should be disabled automatically. This
+/// applies to both the normal and secure locks.
+///
+/// - Expire::Never: The Lock stays until it's disabled.
+/// - Expire::After(secs): Seconds after the Lock was enabled.
+/// - Expire::At(year, month, day, hour, min): Specific time.
+const LOCK_EXPIRE: Expire = Expire::Never;
+
+/// Time (in milliseconds) a button must be held down to be
+/// a long press.
+const GESTURE_LONG: u32 = 750u32;
+/// Time (in milliseconds) after a button is released to wait
+/// for a second press. Only used when the button has a
+/// double press Action configured.
+const GESTURE_DOUBLE: u32 = 350u32;
 // =================== [ Configuration End ] ===================
 
+const BTN_A: u8 = input::BUTTON_A;
+const BTN_B: u8 = input::BUTTON_B;
+const BTN_C: u8 = input::BUTTON_C;
+const BTN_D: u8 = input::BUTTON_D;
+const BTN_E: u8 = input::BUTTON_E;
+
 #[repr(u8)]
 pub enum SideError {
     Byte                   = 0x00u8,
@@ -109,7 +430,16 @@ pub struct SideShow<'a, const B: usize, const W: u16, const H: u16, D: BlockDevi
     inky:  Inky<'a, B, W, H, Static<B>>,
     root:  &'a Volume<'a, D>,
     rand:  Rand,
+    volt:  u16,
+    vbus:  bool,
+    font:  Font,
+    favs:  NameList,
+    hide:  NameList,
+    state: State,
     board: &'a InkyBoard<'a>,
+    // Directories used for the current time of day.
+    badges:      &'static str,
+    backgrounds: &'static str,
 }
 
 #[cfg(feature = "inky5")]
@@ -124,9 +454,48 @@ enum Action {
     Rand,
     Wake,
     Lock,
+    Reset,
+    Home,
+    Last,
+    Jump(u8),
+    SkipBack(u8),
+    SkipForward(u8),
+    BackgroundLock,
+    BackgroundMode,
+    BackgroundNext,
+    Favorite,
+    FavoriteMode,
+    Hide,
+    HideBackground,
+    Cover,
+    Refresh,
+    Status,
+    StatusNext,
+    StatusPrev,
+    ShowQr,
+    ShowBarcode,
+    ShowMoon,
+    Countdown,
     // TODO(sf): Custom Actions
     // Custom,
 }
+enum Backgrounds {
+    Files,
+    Generated,
+    Both,
+    Art,
+}
+enum Caption {
+    None,
+    Name,
+    Sidecar,
+    Template(&'static str),
+}
+enum Expire {
+    Never,
+    After(u32),
+    At(u16, u8, u8, u8, u8),
+}
 enum LoadError {
     DirOpen,
     DirNotFound,
@@ -190,35 +559,84 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
             root,
             inky: i,
             rand: Rand::new(),
+            volt: 0u16,
+            vbus: false,
+            font: Font::Builtin,
+            favs: NameList::new(),
+            hide: NameList::new(),
+            state: State::new(),
             board: b,
+            badges: DIR_BADGES,
+            backgrounds: DIR_BACKGROUNDS,
         })
     }
 
     pub fn run(&mut self) -> MayFail<SideError> {
         let (l, b) = (self.board.leds(), self.board.buttons());
+        self.state = State::load(self.root, FILE_STATE);
+        self.favs = NameList::load(self.root, FILE_FAVORITES);
+        self.hide = NameList::load(self.root, FILE_HIDDEN);
+        self.font = Font::load(self.root, FILE_FONT);
         loop {
+            self.vbus = power::vbus(self.board, BATTERY_USB);
             //
-            out!("loop enter, time: {}", self.board.pcf().now().unwrap());
+            out!("loop enter, vbus={}, time: {}", self.vbus, self.board.pcf().now().unwrap());
             //
             l.all_off();
             // Read PFC register, fallback to 0 if it fails.
-            let s = self.board.pcf().get_byte().unwrap_or(0);
+            let mut s = self.board.pcf().get_byte().unwrap_or(0);
+            // Make sure a lost PCF byte does not undo the secure lock.
+            if self.state.secure() {
+                s |= 0x80;
+            }
+            self.volt = power::vsys(self.board);
+            // VSYS is meaningless on USB power, it's always the VBUS voltage.
+            let o = !self.vbus && self.volt < BATTERY_LOW;
             //
-            out!("PFC byte_read={s}, starting switch..");
+            out!("PFC byte_read={s}, vsys={}mV, starting switch..", self.volt);
             //
-            let n = self.switch(s, l, b)?;
+            let n = if o {
+                self.low_battery(l);
+                s
+            } else {
+                self.switch(s, l, b)?
+            };
             //
             out!("Switch result n={n}, setting PFC byte..");
             //
             l.all_off();
             self.board.pcf().set_byte(n).map_err(|_| SideError::Byte)?;
+            // Not being able to save the settings shouldn't stop the badge, they
+            // only last until the next power cycle.
+            if self.state.save(self.root, FILE_STATE).is_err() {
+                //
+                out!("State save failed, continuing..");
+                //
+            }
             self.board.sleep(2_500);
-            //
-            out!("Setting PFC rtc_wake time..");
-            //
-            let w = self.board.set_rtc_wake(SLEEP_TIME).map_err(|_| SideError::Wake)?;
-            unsafe { self.board.power_off() };
-            // Everything after this means we're on AC power.
+            let w = if o {
+                //
+                out!("Battery is low, disabling PFC rtc_wake..");
+                //
+                // Only a button press can wake us up now. Clear any fired alarm
+                // first, a set alarm flag would keep the board powered.
+                self.board.pcf().alarm_clear_state().map_err(|_| SideError::Wake)?;
+                self.board.pcf().alarm_disable().map_err(|_| SideError::Wake)?;
+                u32::MAX
+            } else {
+                //
+                out!("Setting PFC rtc_wake time..");
+                //
+                self.board
+                    .set_rtc_wake(if self.vbus { SLEEP_TIME_USB } else { SLEEP_TIME })
+                    .map_err(|_| SideError::Wake)?
+            };
+            if !self.vbus {
+                unsafe { self.board.power_off() };
+            }
+            // Everything after this means we're on AC power. This can still
+            // happen if VBUS wasn't detected but we're powered through the
+            // VSYS pin by something else.
             //
             out!("Board is NOT on battery power, running manual sleep..");
             //
@@ -231,8 +649,21 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
 
     fn sleep(&mut self, b: &mut Buttons, w: u32) {
         let mut v = w;
+        // The alarm was just set, anything seen before it is an old one.
+        power::idle_reset();
         while v > 0 {
-            self.board.sleep(SLEEP_STEP);
+            // Low power wait instead of spinning, the core is asleep until the
+            // timer alarm or the PCF RTC alarm interrupt fires. The RTC alarm
+            // wakes us up right away, instead of after our (drifting) countdown.
+            //
+            // NOTE(sf): The buttons are still polled through the shift register
+            //           every step, as it has no interrupt output wired to the
+            //           RP2040. So a press can take up to 'SLEEP_STEP' to be
+            //           seen. Dormant mode isn't used, since it stops the timer
+            //           that wakes us up to poll them.
+            if power::idle(SLEEP_STEP * 1_000) {
+                break;
+            }
             // Watch for button presses.
             if b.read_pressed() {
                 break;
@@ -245,9 +676,444 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
             b.set(Button::RTC);
         }
     }
-    #[inline(always)]
-    fn background(&mut self) -> Result<(), SideError> {
-        self.random_set_image(DIR_BACKGROUNDS).map_err(SideError::background)?;
+    fn low_battery(&mut self, l: &Leds) {
+        l.e.on();
+        // Try the user supplied image first, if that fails, draw the built-in
+        // screen.
+        if self.set_image_file(FILE_LOWBAT).is_err() {
+            let (w, h) = (self.inky.width(), self.inky.height());
+            let s = w / 3;
+            self.inky.fill(Color::White);
+            draw::battery(&mut self.inky, (w / 2 - s / 2) as i32, (h / 2 - s / 4) as i32, s, 0);
+        }
+        self.inky.update();
+    }
+    fn cover(&mut self) {
+        if self.set_image_file(FILE_COVER).is_err() {
+            self.inky.fill(COVER_COLOR);
+        }
+        self.inky.update();
+    }
+    fn status(&mut self, d: i32) {
+        let mut b = [0u8; 1024];
+        let n = files::read(self.root, FILE_STATUS, &mut b).unwrap_or(0);
+        let t = text::valid(&b[0..n]);
+        // The selected index is saved as a byte, so only the first 256 can be
+        // selected.
+        let c = t.lines().filter(|v| !v.trim().is_empty()).count().min(256) as i32;
+        let e = if c == 0 {
+            "No Status"
+        } else {
+            let i = ((self.state.status_index() as i32).min(c - 1) + d).rem_euclid(c);
+            self.state.set_status_index(i as u8);
+            t.lines().filter(|v| !v.trim().is_empty()).nth(i as usize).unwrap_or_default()
+        };
+        let (v, i) = match e.split_once('|') {
+            Some((v, i)) => (v.trim(), Some(i.trim())),
+            None => (e.trim(), None),
+        };
+        if let Some(p) = i {
+            // A missing icon shouldn't stop the status from showing.
+            let _ = self.set_image_file(p);
+        }
+        self.board_text(v);
+    }
+    /// Draw the text 'v' as large as it fits in the center of the display, for
+    /// the status board and countdown modes.
+    fn board_text(&mut self, v: &str) {
+        let (w, h) = (self.inky.width(), self.inky.height());
+        let (bw, bh) = (w.saturating_sub(64), h.saturating_sub(64));
+        // Shrink the text until it all fits on the display.
+        let mut s = STATUS_SCALE.max(1);
+        while s > 1 && self.font.height(v, bw, s) > bh {
+            s -= 1;
+        }
+        let th = self.font.height(v, bw, s).min(bh);
+        let y = (h - th) as i32 / 2;
+        if let Some(c) = STATUS_BOX {
+            self.inky.rect(16, y - 16, w.saturating_sub(32), th + 32, c);
+        }
+        self.font.draw_box(&mut self.inky, 32, y, bw, th, v, s, STATUS_COLOR, &Align::Center);
+    }
+    fn qr(&mut self) -> Result<(), SideError> {
+        self.inky.fill(Color::White);
+        if QR_BACKGROUND {
+            self.background(&Action::Refresh)?;
+        }
+        let mut b = [0u8; 512];
+        let n = files::read(self.root, FILE_QR, &mut b).unwrap_or(0);
+        // Trailing newlines are common in text files, but would be part of
+        // the code.
+        let v = text::valid(&b[0..n]).trim();
+        match QrCode::encode(v.as_bytes(), &QR_ECC) {
+            Some(q) if !v.is_empty() => {
+                let t = q.size() + QR_QUIET * 2;
+                let s = if QR_SCALE > 0 {
+                    QR_SCALE
+                } else {
+                    (self.inky.width().min(self.inky.height()).saturating_sub(draw::MARGIN * 2) / t).max(1)
+                };
+                let (x, y) = draw::place(&self.inky, &QR_POSITION, t * s, t * s);
+                q.draw(&mut self.inky, x, y, s, QR_QUIET);
+            },
+            // Missing, or too long to fit in a QR code.
+            _ => text::label(&mut self.inky, &self.font, "No QR Code", &Position::Center, 4, Color::Black, Some(Color::White)),
+        }
+        Ok(())
+    }
+    fn clock(&mut self) {
+        if !CLOCK && CLOCK_STAMP.is_none() {
+            return;
+        }
+        let t = match self.board.pcf().now() {
+            Ok(t) => t,
+            Err(_) => return,
+        };
+        if CLOCK {
+            let mut b = TextBuf::<128>::new();
+            let _ = calendar::format(&mut b, &t, CLOCK_FORMAT, CLOCK_24H);
+            text::label(&mut self.inky, &self.font, b.as_str(), &CLOCK_POSITION, CLOCK_SCALE, CLOCK_COLOR, CLOCK_BOX);
+        }
+        if let Some(f) = CLOCK_STAMP {
+            let mut b = TextBuf::<128>::new();
+            let _ = calendar::format(&mut b, &t, f, CLOCK_24H);
+            text::label(&mut self.inky, &self.font, b.as_str(), &CLOCK_STAMP_POSITION, 1, CLOCK_COLOR, CLOCK_BOX);
+        }
+    }
+    fn moon(&mut self) {
+        if !MOON {
+            return;
+        }
+        let mut b = TextBuf::<128>::new();
+        let a = match self.moon_phase(&mut b) {
+            Some(v) => v,
+            None => return,
+        };
+        let (r, d) = (MOON_SIZE / 2, 2u16 * MOON_SCALE.max(1));
+        let (w, h) = self.font.size(b.as_str(), self.inky.width() / 2, MOON_SCALE);
+        // The icon is on the left, with the text next to it.
+        let (bw, bh) = (r * 2 + w + d * 3, (r * 2).max(h) + d * 2);
+        let (x, y) = draw::place(&self.inky, &MOON_POSITION, bw, bh);
+        if let Some(c) = MOON_BOX {
+            self.inky.rect(x, y, bw, bh, c);
+        }
+        draw::moon(&mut self.inky, x + d as i32, y + ((bh - r * 2) / 2) as i32, r, a);
+        let (tx, ty) = (x + (r * 2 + d * 2) as i32, y + ((bh - h) / 2) as i32);
+        self.font.draw_box(&mut self.inky, tx, ty, w, h, b.as_str(), MOON_SCALE, MOON_COLOR, &Align::Left);
+    }
+    fn moon_screen(&mut self) {
+        self.inky.fill(Color::White);
+        let mut b = TextBuf::<128>::new();
+        let a = match self.moon_phase(&mut b) {
+            Some(v) => v,
+            None => return text::label(&mut self.inky, &self.font, "No Time", &Position::Center, 4, Color::Black, None),
+        };
+        let (w, h) = (self.inky.width(), self.inky.height());
+        let r = h / 4;
+        draw::moon(&mut self.inky, (w / 2 - r) as i32, (h / 8) as i32, r, a);
+        text::label(&mut self.inky, &self.font, b.as_str(), &Position::Bottom, 3, Color::Black, None);
+    }
+    /// Write the moon phase text to 'b' and return the phase angle, or None
+    /// if the time can't be read.
+    fn moon_phase(&self, b: &mut TextBuf<128>) -> Option<i32> {
+        let t = self.board.pcf().now().ok()?;
+        let m = Moon::at(calendar::timestamp(&t) as i64 - TIMEZONE as i64 * 60i64);
+        let _ = write!(b, "{}\n{}% lit", m.name(), m.lit());
+        if let (true, Some((y, x))) = (MOON_DAY_LENGTH, LOCATION) {
+            let v = match sky::sun(&Date::from_time(&t), y, x) {
+                Sun::Times(r, s) => s - r,
+                Sun::Up => 86_400i64,
+                Sun::Down => 0i64,
+            };
+            let _ = write!(b, "\nDay {}h {:02}m", v / 3_600, (v % 3_600) / 60);
+        }
+        Some(m.angle())
+    }
+    fn caption(&mut self, sel: u8) {
+        if matches!(CAPTION, Caption::None) {
+            return;
+        }
+        let mut b = [0u8; 128];
+        let v = match self.stem(sel, &mut b) {
+            Some(v) => v,
+            None => return,
+        };
+        let mut t = TextBuf::<256>::new();
+        match CAPTION {
+            Caption::None => (),
+            Caption::Name => t.push(v),
+            Caption::Sidecar => self.sidecar(v, "txt", &mut t),
+            Caption::Template(s) => {
+                let mut r = s;
+                while let Some((a, x)) = r.split_once('{') {
+                    t.push(a);
+                    let (k, x) = match x.split_once('}') {
+                        Some(e) => e,
+                        None => {
+                            // No closing brace, keep it as is.
+                            t.push("{");
+                            r = x;
+                            continue;
+                        },
+                    };
+                    match k {
+                        "name" => t.push(v),
+                        "caption" => self.sidecar(v, "txt", &mut t),
+                        "date" => {
+                            if let Ok(n) = self.board.pcf().now() {
+                                let d = Date::from_time(&n);
+                                let _ = write!(t, "{:04}-{:02}-{:02}", d.year, d.month, d.day);
+                            }
+                        },
+                        "time" => {
+                            if let Ok(n) = self.board.pcf().now() {
+                                let _ = write!(t, "{:02}:{:02}", n.hours, n.mins);
+                            }
+                        },
+                        // Keep unknown tokens, they're probably meant to be
+                        // there.
+                        _ => {
+                            let _ = write!(t, "{{{k}}}");
+                        },
+                    }
+                    r = x;
+                }
+                t.push(r);
+            },
+        }
+        text::label(&mut self.inky, &self.font, t.as_str().trim(), &CAPTION_POSITION, CAPTION_SCALE, CAPTION_COLOR, CAPTION_BOX);
+    }
+    fn barcode_overlay(&mut self, sel: u8) {
+        if !BARCODE_OVERLAY {
+            return;
+        }
+        let mut t = TextBuf::<256>::new();
+        match BARCODE_TEXT {
+            Some(v) => t.push(v),
+            None => {
+                let mut b = [0u8; 128];
+                if let Some(v) = self.stem(sel, &mut b) {
+                    self.sidecar(v, "code", &mut t);
+                }
+            },
+        }
+        // Badges without a barcode are left as is.
+        if let Some(c) = Barcode::encode(t.as_str(), &BARCODE_TYPE) {
+            self.barcode(&c, t.as_str(), &BARCODE_POSITION);
+        }
+    }
+    fn barcode_screen(&mut self) {
+        self.inky.fill(Color::White);
+        let mut b = [0u8; 256];
+        let v = match BARCODE_TEXT {
+            Some(v) => v,
+            None => {
+                let n = files::read(self.root, FILE_BARCODE, &mut b).unwrap_or(0);
+                text::valid(&b[0..n]).trim()
+            },
+        };
+        match Barcode::encode(v, &BARCODE_TYPE) {
+            Some(c) => self.barcode(&c, v, &Position::Center),
+            // Missing, or has characters the barcode can't hold.
+            None => text::label(&mut self.inky, &self.font, "No Barcode", &Position::Center, 4, Color::Black, Some(Color::White)),
+        }
+    }
+    fn barcode(&mut self, c: &Barcode, v: &str, p: &Position) {
+        let t = c.len() + BARCODE_QUIET * 2;
+        let s = if BARCODE_SCALE > 0 {
+            BARCODE_SCALE
+        } else {
+            (self.inky.width().saturating_sub(draw::MARGIN * 2) / t).max(1)
+        };
+        // Room for the text under the bars, and some padding above and below.
+        let l = if BARCODE_LABEL { self.font.line_height(2).saturating_add(4) } else { 0 };
+        let (w, h) = (t * s, BARCODE_HEIGHT.saturating_add(l).saturating_add(8));
+        let (x, y) = draw::place(&self.inky, p, w, h);
+        self.inky.rect(x, y, w, h, Color::White);
+        c.draw(&mut self.inky, x, y + 4, s, BARCODE_HEIGHT, BARCODE_QUIET);
+        if BARCODE_LABEL {
+            let y = y + BARCODE_HEIGHT as i32 + 8;
+            self.font.draw_box(&mut self.inky, x, y, w, l, v, 2, Color::Black, &Align::Center);
+        }
+    }
+    /// Returns the file name of the badge at 'sel', without the extension.
+    fn stem<'b>(&self, sel: u8, buf: &'b mut [u8]) -> Option<&'b str> {
+        // Template badges don't have files.
+        if TEMPLATE {
+            return None;
+        }
+        let n = self.name_at(self.badges, (sel & 0x7F) as usize, buf).ok()?;
+        let v = text::valid(&buf[0..n]);
+        Some(v.rsplit_once('.').map_or(v, |x| x.0))
+    }
+    fn sidecar(&self, name: &str, ext: &str, t: &mut TextBuf<256>) {
+        let mut p = TextBuf::<160>::new();
+        let _ = write!(p, "{DIR_CAPTIONS}/{name}.{ext}");
+        let mut b = [0u8; 256];
+        // A missing sidecar is just empty text.
+        let n = files::read(self.root, p.as_str(), &mut b).unwrap_or(0);
+        t.push(text::valid(&b[0..n]).trim());
+    }
+    fn countdown(&mut self) {
+        let mut b = [0u8; 1024];
+        let n = files::read(self.root, FILE_EVENTS, &mut b).unwrap_or(0);
+        let now = self.board.pcf().now().map_or(0, |t| calendar::timestamp(&t));
+        // Find the latest event that passed and the next one to come.
+        let (mut p, mut u) = (None, None);
+        for l in text::valid(&b[0..n]).lines() {
+            let t = match l.split('|').next().and_then(|v| calendar::parse(v.trim())) {
+                Some(t) => t,
+                None => continue,
+            };
+            if t > now {
+                if u.is_none_or(|(x, _)| t < x) {
+                    u = Some((t, l));
+                }
+            } else if p.is_none_or(|(x, _)| t > x) {
+                p = Some((t, l));
+            }
+        }
+        // Keep showing a passed event for a bit, before moving to the next.
+        let (t, l) = match (p, u) {
+            (Some(v), _) if now - v.0 < COUNTDOWN_KEEP => v,
+            (_, Some(v)) | (Some(v), None) => v,
+            (None, None) => return self.board_text("No Events"),
+        };
+        let mut v = l.split('|').skip(1);
+        let (e, a) = (v.next().unwrap_or_default().trim(), v.next().unwrap_or_default().trim());
+        let mut s = TextBuf::<256>::new();
+        if t > now {
+            let d = t - now;
+            let (x, y, z) = (d / 86_400, (d % 86_400) / 3_600, (d % 3_600) / 60);
+            if x > 0 {
+                unit(&mut s, x, "day");
+                if y > 0 {
+                    s.push(", ");
+                    unit(&mut s, y, "hour");
+                }
+            } else if y > 0 {
+                unit(&mut s, y, "hour");
+                if z > 0 {
+                    s.push(", ");
+                    unit(&mut s, z, "minute");
+                }
+            } else {
+                unit(&mut s, z.max(1), "minute");
+            }
+            let _ = write!(s, "\nuntil {e}");
+            return self.board_text(s.as_str());
+        }
+        // The image is drawn instead of any text. If it can't be loaded, the
+        // message is used.
+        if a.starts_with('/') && self.set_image_file(a).is_ok() {
+            return;
+        }
+        if !a.is_empty() && !a.starts_with('/') {
+            s.push(a);
+        } else {
+            match COUNTDOWN_AFTER.split_once("{event}") {
+                Some((x, y)) => {
+                    s.push(x);
+                    s.push(e);
+                    s.push(y);
+                },
+                None => s.push(COUNTDOWN_AFTER),
+            }
+        }
+        self.board_text(s.as_str());
+    }
+    fn daylight(&mut self) {
+        let (y, x) = match LOCATION {
+            Some(v) => v,
+            None => return,
+        };
+        let n = match self.board.pcf().now() {
+            Ok(t) => {
+                // The RTC is in local time, the sun times are in UTC.
+                let v = calendar::timestamp(&t) as i64 - TIMEZONE as i64 * 60i64;
+                match sky::sun(&Date::from_time(&t), y, x) {
+                    Sun::Times(r, s) => v < r || v >= s,
+                    Sun::Up => false,
+                    Sun::Down => true,
+                }
+            },
+            Err(_) => false,
+        };
+        // Missing or empty night directories fall back to the day ones.
+        self.badges = if n && self.count(DIR_BADGES_NIGHT).unwrap_or(0) > 0 { DIR_BADGES_NIGHT } else { DIR_BADGES };
+        self.backgrounds = if n && self.count(DIR_BACKGROUNDS_NIGHT).unwrap_or(0) > 0 { DIR_BACKGROUNDS_NIGHT } else { DIR_BACKGROUNDS };
+    }
+    fn dated(&mut self) -> bool {
+        let d = match self.board.pcf().now() {
+            Ok(t) => Date::from_time(&t),
+            Err(_) => return false,
+        };
+        // The exact date is checked before the yearly one, so a single year can
+        // be different.
+        let mut p = TextBuf::<64>::new();
+        let _ = write!(p, "{DIR_DATES}/{:04}-{:02}-{:02}.tga", d.year, d.month, d.day);
+        if self.set_image_file(p.as_str()).is_ok() {
+            return true;
+        }
+        let mut p = TextBuf::<64>::new();
+        let _ = write!(p, "{DIR_DATES}/{:02}-{:02}.tga", d.month, d.day);
+        if self.set_image_file(p.as_str()).is_ok() {
+            return true;
+        }
+        let mut b = [0u8; 1024];
+        let n = files::read(self.root, FILE_DATES, &mut b).unwrap_or(0);
+        for l in text::valid(&b[0..n]).lines() {
+            match l.split_once('|') {
+                Some((r, v)) if calendar::within(r.trim(), &d) && self.set_image_file(v.trim()).is_ok() => return true,
+                _ => (),
+            }
+        }
+        false
+    }
+    fn set_image_file(&mut self, path: &str) -> Result<(), LoadError> {
+        let mut f = unsafe { self.root.file_open(path, Mode::READ)?.into_reader().unwrap_unchecked() };
+        // SAFETY: If opened with 'Mode::READ', 'into_reader' never fails.
+        self.inky.set_with(|x| x.set_image(0, 0, TgaParser::new(&mut f)?))?;
+        Ok(())
+    }
+    fn background(&mut self, a: &Action) -> Result<(), SideError> {
+        let c = self.state.background() as usize;
+        let (k, q) = (self.state.background_lock(), self.state.background_sequence());
+        // A missing or empty directory falls back to the generated backgrounds.
+        let f = match BACKGROUNDS {
+            Backgrounds::Generated | Backgrounds::Art => 0,
+            _ => self.count(self.backgrounds).unwrap_or(0),
+        };
+        let g = if matches!(BACKGROUNDS, Backgrounds::Art) { pattern::ART } else { 0 };
+        // The generated backgrounds are placed after the files.
+        let n = f + if f == 0 || matches!(BACKGROUNDS, Backgrounds::Both) { pattern::COUNT - g } else { 0 };
+        let (i, s) = match a {
+            // BackgroundNext: Always advance, even if locked.
+            Action::BackgroundNext => ((c + 1) % n, false),
+            // HideBackground: The hidden one is removed, so the same index
+            //                 is the next one.
+            // Refresh: Keep the same background.
+            Action::HideBackground | Action::Refresh => (c % n, true),
+            // Locked: Keep the same background. The count may have changed
+            //         so make sure it's still in range.
+            _ if k => (c % n, true),
+            _ if q => ((c + 1) % n, false),
+            _ => (self.rand.rand_u32n(n as u32) as usize, false),
+        };
+        if i < f {
+            self.set_image_with(self.backgrounds, |_, _| i).map_err(SideError::background)?;
+        } else {
+            // Kept backgrounds are drawn the same way again, anything else gets
+            // new colors.
+            if !s {
+                // Mix in the time, so a repeated random value still makes a new
+                // background without counting the wake-ups on the SD Card.
+                let t = self.board.pcf().now().map_or(0, |t| calendar::timestamp(&t));
+                self.state.set_background_seed(self.rand.rand_u32n(u32::MAX) ^ t.wrapping_mul(0x9E37_79B9));
+            }
+            pattern::draw(&mut self.inky, g + i - f, self.state.background_seed());
+        }
+        self.state.set_background(i as u16);
         Ok(())
     }
     fn badge(&mut self, act: Action, cur: u8) -> Result<u8, LoadError> {
@@ -255,33 +1121,71 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
             Action::None => return Ok(cur), // Just in case.
             // Random: Override the Badge lock and set a random one. Set this
             //         new badge position as the index, without the lock on.
-            Action::Rand => return Ok(self.random_set_image(DIR_BADGES)? as u8),
+            Action::Rand if TEMPLATE => {
+                let n = self.template_count()?;
+                let i = self.rand.rand_u32n(n.max(1) as u32) as u8;
+                return self.template(i);
+            },
+            Action::Rand => return Ok(self.set_image_with(self.badges, |r, n| r.rand_u32n(n as u32) as usize)? as u8),
             // Wake: Don't change the badge selected if the lock is on, if it's
             //       off, act like Next.
             // Next: Don't change the badge selected if the lock is on, if it's
             //       off, select the next badge, wrapping if over the badge count.
             // Prev: Don't change the badge selected if the lock is on, if it's
             //       off, select the last badge, resetting to the max if zero.
+            // Home/Last/Jump/Skip*: Don't change the badge selected if the lock
+            //                       is on, if it's off, select the badge by
+            //                       position.
             _ => (),
         }
-        let n = cur & 0x7F;
+        let mut n = cur & 0x7F;
+        // The max value means the last badge, but we don't know it's position.
+        // Find it so going backwards works.
+        if n == 0x7F && cur & 0x80 == 0 && matches!(act, Action::Prev | Action::SkipBack(_)) {
+            let c = if TEMPLATE { self.template_count()? } else { self.count(self.badges)? };
+            n = (c.saturating_sub(1)).min(0x7F) as u8;
+        }
+        // The index can be the real position of the last badge (eg: after a Jump).
+        // Mark it as the last so skipping forward wraps, like Next.
+        if n < 0x7F && cur & 0x80 == 0 && matches!(act, Action::SkipForward(_)) {
+            let c = if TEMPLATE { self.template_count()? } else { self.count(self.badges)? };
+            if n as usize + 1 >= c {
+                n = 0x7F;
+            }
+        }
+        // Reset: Clear the lock and go back to the first badge.
+        let cur = if matches!(act, Action::Reset) { 0 } else { cur };
         let k = match act {
-            _ if cur & 0x80 != 0 => n,                          // All stay the same when the lock is enabled.
-            Action::Next | Action::Wake if n >= 0x7F => 0,      // Wrap and reset.
-            Action::Next | Action::Wake => n.saturating_add(1), // Advance the count.
-            Action::Prev if n == 0 => 0x7F,                     // Reset to the max.
-            Action::Prev => n.saturating_sub(1),                // Reduce the count.
-            _ => unreachable!(),                                // Can't happen.
+            Action::Reset => 0,                                      // Start from the beginning.
+            Action::BackgroundNext | Action::HideBackground => n,    // Only the background changes.
+            Action::Hide => n,                                       // The hidden badge is filtered out, so this is the next one.
+            Action::Refresh => n,                                    // Redraw the same badge.
+            _ if cur & 0x80 != 0 => n,                               // All stay the same when the lock is enabled.
+            Action::Next | Action::Wake if n >= 0x7F => 0,           // Wrap and reset.
+            Action::Next | Action::Wake => n.saturating_add(1),      // Advance the count.
+            Action::Prev if n == 0 => 0x7F,                          // Reset to the max.
+            Action::Prev => n.saturating_sub(1),                     // Reduce the count.
+            Action::Home => 0,                                       // First badge.
+            Action::Last => 0x7F,                                    // Last badge, the max is always clamped.
+            Action::Jump(v) => v.min(0x7F),                          // Select the badge directly.
+            Action::SkipForward(_) if n >= 0x7F => 0,                // Wrap and reset, like Next.
+            Action::SkipForward(v) => n.saturating_add(v).min(0x7F), // Advance, stopping at the last badge.
+            Action::SkipBack(_) if n == 0 => 0x7F,                   // Reset to the max, like Prev.
+            Action::SkipBack(v) => n.saturating_sub(v),              // Reduce, stopping at the first badge.
+            _ => unreachable!(),                                     // Can't happen.
         };
-        let i = {
-            let d = self.root.dir_open(DIR_BADGES)?;
+        let i = if TEMPLATE {
+            self.template(k)?
+        } else {
+            let d = self.root.dir_open(self.badges)?;
+            let x = filter(self.badges, &self.state, &self.favs, &self.hide);
             // Use the 'peekable' iter so we can check if the number goes out of
             // bounds so we can fix the max.
             let mut v = d
                 .list()
                 .map_err(|_| LoadError::FileOpen)?
                 .into_iter()
-                .filter(|e| e.as_ref().is_ok_and(|v| v.is_file()))
+                .filter(|e| e.as_ref().is_ok_and(|v| v.is_file() && x.allows(v.name())))
                 .peekable();
             let mut i = 0u8;
             // Use a loop so we can pull back to make sure we catch the end value.
@@ -308,15 +1212,62 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
         };
         Ok((cur & 0x80) | i)
     }
-    fn random_set_image(&mut self, dir: &str) -> Result<usize, LoadError> {
+    fn template(&mut self, k: u8) -> Result<u8, LoadError> {
+        let n = self.template_count()?;
+        if n == 0 {
+            return Err(LoadError::DirIter);
+        }
+        let i = (k as usize).min(n - 1);
+        // The image is optional, the fields can be drawn on the background.
+        let _ = self.set_image_file(FILE_TEMPLATE_IMAGE);
+        let (mut h, mut r, mut l) = ([0u8; 256], [0u8; 256], [0u8; 1024]);
+        // Skip the header line.
+        let a = files::line(self.root, FILE_TEMPLATE_FIELDS, 0, &mut h)?.unwrap_or(0);
+        let b = files::line(self.root, FILE_TEMPLATE_FIELDS, i + 1, &mut r)?.unwrap_or(0);
+        let c = files::read(self.root, FILE_TEMPLATE_LAYOUT, &mut l)?;
+        template::render(&mut self.inky, &self.font, text::valid(&l[0..c]), text::valid(&h[0..a]), text::valid(&r[0..b]));
+        // Same as the badges, the last one is the max.
+        Ok(if i + 1 >= n || i >= 0x7F { 0x7F } else { i as u8 })
+    }
+    #[inline]
+    fn template_count(&self) -> Result<usize, LoadError> {
+        // Don't count the header line.
+        Ok(files::line_count(self.root, FILE_TEMPLATE_FIELDS)?.saturating_sub(1))
+    }
+    fn count(&self, dir: &str) -> Result<usize, LoadError> {
+        let d = self.root.dir_open(dir)?;
+        let x = filter(dir, &self.state, &self.favs, &self.hide);
+        let mut l = d.list().map_err(|_| LoadError::DirList)?;
+        Ok(l.into_iter_mut().filter(|e| e.as_ref().is_ok_and(|v| v.is_file() && x.allows(v.name()))).count())
+    }
+    fn name_at(&self, dir: &str, i: usize, buf: &mut [u8]) -> Result<usize, LoadError> {
         let d = self.root.dir_open(dir)?;
+        let x = filter(dir, &self.state, &self.favs, &self.hide);
         let mut l = d.list().map_err(|_| LoadError::DirList)?;
-        let n = l.into_iter_mut().filter(|e| e.as_ref().is_ok_and(|v| v.is_file())).count();
+        // Positions past the end (like 0x7F) are the last entry.
+        let e = l
+            .into_iter_mut()
+            .filter_map(|e| e.ok())
+            .filter(|v| v.is_file() && x.allows(v.name()))
+            .take(i.saturating_add(1))
+            .last()
+            .ok_or(LoadError::DirIter)?;
+        let n = e.name().as_bytes();
+        let c = n.len().min(buf.len());
+        buf[0..c].copy_from_slice(&n[0..c]);
+        Ok(c)
+    }
+    fn set_image_with(&mut self, dir: &str, f: impl FnOnce(&mut Rand, usize) -> usize) -> Result<usize, LoadError> {
+        let d = self.root.dir_open(dir)?;
+        let x = filter(dir, &self.state, &self.favs, &self.hide);
+        let mut l = d.list().map_err(|_| LoadError::DirList)?;
+        let n = l.into_iter_mut().filter(|e| e.as_ref().is_ok_and(|v| v.is_file() && x.allows(v.name()))).count();
         l.reset(&d).map_err(|_| LoadError::DirListReset)?;
-        let i = self.rand.rand_u32n(n as u32) as usize;
+        // Don't ask for an index if there's nothing to select.
+        let i = if n == 0 { 0 } else { f(&mut self.rand, n) };
         let e = l
             .into_iter_mut()
-            .filter(|e| e.as_ref().is_ok_and(|v| v.is_file()))
+            .filter(|e| e.as_ref().is_ok_and(|v| v.is_file() && x.allows(v.name())))
             .nth(i)
             .map(|v| v.ok())
             .flatten();
@@ -334,33 +1285,206 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
         self.inky.set_with(|x| x.set_image(0, 0, TgaParser::new(&mut f)?))?;
         Ok(i)
     }
+    fn chord(&self, b: &mut Buttons, p: &Button, l: &Leds) -> Option<Action> {
+        let m = input::mask(p);
+        // Don't wait for other buttons when the first one can't start a chord.
+        if m == 0 || !CHORDS.iter().any(|x| x.0 & m != 0) {
+            return None;
+        }
+        let v = input::chord(self.board, b, m, CHORD_WAIT);
+        if v == m {
+            return None;
+        }
+        // Light up all the buttons in the chord.
+        if v & BTN_A != 0 {
+            l.a.on();
+        }
+        if v & BTN_B != 0 {
+            l.b.on();
+        }
+        if v & BTN_C != 0 {
+            l.c.on();
+        }
+        if v & BTN_D != 0 {
+            l.d.on();
+        }
+        if v & BTN_E != 0 {
+            l.e.on();
+        }
+        // Unknown chords are ignored instead of being treated as the first
+        // button, since it was likely a miss-press.
+        Some(CHORDS.into_iter().find(|x| x.0 == v).map_or(Action::None, |x| x.1))
+    }
     #[inline]
-    fn switch(&mut self, sel: u8, l: &Leds, b: &mut Buttons) -> Result<u8, SideError> {
-        // Check if any button was pressed.
-        let a = match b.pressed() {
-            Button::ButtonA => {
-                l.a.on();
-                BUTTON_A
-            },
-            Button::ButtonB => {
-                l.b.on();
-                BUTTON_B
-            },
-            Button::ButtonC => {
-                l.c.on();
-                BUTTON_C
-            },
-            Button::ButtonD => {
-                l.d.on();
-                BUTTON_D
+    fn gesture(&self, b: &mut Buttons, m: u8, short: Action, long: Action, double: Action) -> Action {
+        // Skip the gesture check if nothing is bound to them, so there's no
+        // delay waiting for a double press.
+        if matches!(long, Action::None) && matches!(double, Action::None) {
+            return short;
+        }
+        // Don't wait for a double press if it won't do anything.
+        let d = if matches!(double, Action::None) { 0 } else { GESTURE_DOUBLE };
+        // A gesture without an Action is treated as a normal press, so the
+        // button isn't ignored when only the other gesture is bound.
+        match input::gesture(self.board, b, m, GESTURE_LONG, d) {
+            Press::Long if !matches!(long, Action::None) => long,
+            Press::Double if !matches!(double, Action::None) => double,
+            _ => short,
+        }
+    }
+    fn lock_expire(&self) -> u32 {
+        match LOCK_EXPIRE {
+            Expire::Never => 0,
+            Expire::After(v) => match self.board.pcf().now() {
+                // Make sure it's never zero, as that means no expiry.
+                Ok(t) => calendar::timestamp(&t).saturating_add(v).max(1),
+                // Can't tell when, so don't expire.
+                Err(_) => 0,
             },
-            Button::ButtonE => {
-                l.e.on();
-                BUTTON_E
+            Expire::At(y, m, d, h, n) => calendar::seconds(&Date::new(y, m, d), h, n, 0).max(1),
+        }
+    }
+    fn unlock_expired(&mut self, sel: u8) -> u8 {
+        let e = self.state.expire();
+        if e == 0 || sel & 0x80 == 0 {
+            return sel;
+        }
+        match self.board.pcf().now() {
+            Ok(t) if calendar::timestamp(&t) >= e => (),
+            _ => return sel,
+        }
+        //
+        out!("Lock expired (expire={e}), removing lock..");
+        //
+        self.state.set_secure(false);
+        self.state.set_expire(0);
+        sel & 0x7F
+    }
+    fn flash_state(&self, l: &Leds, v: bool) {
+        for _ in 0..3 {
+            l.network.on();
+            l.activity.on();
+            self.board.sleep(250);
+            l.network.off();
+            l.activity.off();
+            self.board.sleep(250);
+        }
+        if v {
+            l.network.on();
+        } else {
+            l.activity.on();
+        }
+        self.board.sleep(2_000);
+    }
+    fn unlock(&mut self, b: &mut Buttons, m: u8, sel: u8, l: &Leds) -> u8 {
+        // The whole sequence is read while awake, so the progress is only kept
+        // here instead of writing it to the SD Card on every press.
+        let (mut i, mut m) = (0usize, m);
+        loop {
+            if LOCK_PIN[i] == m {
+                i += 1;
+                // Quick blink to acknowledge the press.
+                l.activity.on();
+                self.board.sleep(250);
+                l.activity.off();
+            } else {
+                // Wrong button, start over. Let this press count as the start
+                // of the next attempt if it matches.
+                i = if LOCK_PIN[0] == m { 1 } else { 0 };
+                for _ in 0..3 {
+                    l.all_on();
+                    self.board.sleep(200);
+                    l.all_off();
+                    self.board.sleep(200);
+                }
+            }
+            if i >= LOCK_PIN.len() {
+                break;
+            }
+            m = input::next(self.board, b, m, LOCK_WAIT);
+            // Nothing pressed in time, drop the attempt.
+            if m == 0 {
+                return sel;
+            }
+        }
+        self.state.set_secure(false);
+        self.state.set_expire(0);
+        // Indicate lock is off.
+        l.network.off();
+        l.activity.on();
+        self.board.sleep(2_000);
+        sel & 0x7F
+    }
+    #[inline]
+    fn switch(&mut self, mut sel: u8, l: &Leds, b: &mut Buttons) -> Result<u8, SideError> {
+        // Check if any button was pressed.
+        let p = b.pressed();
+        // The secure lock ignores all buttons, besides the unlock sequence.
+        if self.state.secure() && input::mask(&p) != 0 {
+            return Ok(self.unlock(b, input::mask(&p), sel, l));
+        }
+        let mut a = match self.chord(b, &p, l) {
+            Some(v) => v,
+            None => match p {
+                Button::ButtonA => {
+                    l.a.on();
+                    self.gesture(b, input::BUTTON_A, BUTTON_A, BUTTON_A_LONG, BUTTON_A_DOUBLE)
+                },
+                Button::ButtonB => {
+                    l.b.on();
+                    self.gesture(b, input::BUTTON_B, BUTTON_B, BUTTON_B_LONG, BUTTON_B_DOUBLE)
+                },
+                Button::ButtonC => {
+                    l.c.on();
+                    self.gesture(b, input::BUTTON_C, BUTTON_C, BUTTON_C_LONG, BUTTON_C_DOUBLE)
+                },
+                Button::ButtonD => {
+                    l.d.on();
+                    self.gesture(b, input::BUTTON_D, BUTTON_D, BUTTON_D_LONG, BUTTON_D_DOUBLE)
+                },
+                Button::ButtonE => {
+                    l.e.on();
+                    self.gesture(b, input::BUTTON_E, BUTTON_E, BUTTON_E_LONG, BUTTON_E_DOUBLE)
+                },
+                Button::None => Action::None,
+                Button::RTC | Button::External => Action::Wake,
             },
-            Button::None => Action::None,
-            Button::RTC | Button::External => Action::Wake,
         };
+        // Cover mode ignores everything until it's disabled.
+        if self.state.cover() {
+            if !matches!(a, Action::Cover) {
+                return Ok(sel);
+            }
+            //
+            out!("Cover disabled, restoring display..");
+            //
+            self.state.set_cover(false);
+            a = Action::Refresh;
+        } else if matches!(a, Action::Cover) {
+            //
+            out!("Cover enabled..");
+            //
+            l.network.on();
+            // Turning on the cover saves the State, which has the index and seed
+            // of the background on the display, so the Refresh when it's turned
+            // off draws the same one, even after a power off.
+            self.state.set_cover(true);
+            self.cover();
+            return Ok(sel);
+        }
+        self.daylight();
+        // The status board uses the navigation buttons to select the status.
+        if self.state.status() {
+            a = match a {
+                Action::Next => Action::StatusNext,
+                Action::Prev => Action::StatusPrev,
+                v => v,
+            };
+        }
+        // RTC wake-ups check if the lock should be removed.
+        if matches!(a, Action::Wake) {
+            sel = self.unlock_expired(sel);
+        }
         // Signal online.
         l.activity.on();
         match a {
@@ -370,17 +1494,157 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
                     // Indicate lock is off.
                     l.network.off();
                     l.activity.on();
+                    self.state.set_expire(0);
                     sel & 0x7F
                 } else {
                     // Indicate lock is on.
                     l.network.on();
                     l.activity.off();
+                    if LOCK_SECURE {
+                        self.state.set_secure(true);
+                    }
+                    self.state.set_expire(self.lock_expire());
                     sel | 0x80
                 };
                 // Let the user know it was changed.
                 self.board.sleep(2_000);
                 return Ok(v);
             },
+            Action::BackgroundLock | Action::BackgroundMode => {
+                let v = if matches!(a, Action::BackgroundLock) {
+                    let v = !self.state.background_lock();
+                    self.state.set_background_lock(v);
+                    v
+                } else {
+                    let v = !self.state.background_sequence();
+                    self.state.set_background_sequence(v);
+                    v
+                };
+                // Flash the LEDs to show it's for the background, then show
+                // the new state the same as the Lock.
+                self.flash_state(l, v);
+                return Ok(sel);
+            },
+            Action::Favorite => {
+                let mut n = [0u8; 64];
+                let c = self.name_at(self.badges, (sel & 0x7F) as usize, &mut n).map_err(SideError::badge)?;
+                match self.favs.toggle(&n[0..c]) {
+                    // The save failed, so go back to the list on the SD Card and
+                    // light up all the LEDs, like when the list is full.
+                    Some(_) if self.favs.save(self.root, FILE_FAVORITES).is_err() => {
+                        self.favs = NameList::load(self.root, FILE_FAVORITES);
+                        l.all_on();
+                    },
+                    Some(v) => {
+                        // Same as the Lock, 'On' means it was added.
+                        if v {
+                            l.network.on();
+                            l.activity.off();
+                        } else {
+                            l.network.off();
+                            l.activity.on();
+                        }
+                    },
+                    // The list is full.
+                    None => l.all_on(),
+                }
+                self.board.sleep(2_000);
+                return Ok(sel);
+            },
+            Action::FavoriteMode => {
+                let v = !self.state.favorites();
+                self.state.set_favorites(v);
+                self.flash_state(l, v);
+                // The badge positions are different between the modes, so start
+                // over from the first badge, keeping the lock.
+                sel &= 0x80;
+                a = Action::Home;
+            },
+            Action::Hide | Action::HideBackground => {
+                let (d, i, e): (_, _, fn(LoadError) -> SideError) = if matches!(a, Action::Hide) {
+                    (self.badges, (sel & 0x7F) as usize, SideError::badge)
+                } else {
+                    (self.backgrounds, self.state.background() as usize, SideError::background)
+                };
+                // Generated backgrounds don't have a file to hide, and the last
+                // visible badge is kept, so there's always one to display.
+                let c = self.count(d).unwrap_or(0);
+                let x = if matches!(a, Action::Hide) { c <= 1 } else { i >= c };
+                if x {
+                    l.all_on();
+                    self.board.sleep(2_000);
+                    return Ok(sel);
+                }
+                let (mut n, mut p) = ([0u8; 64], [0u8; 128]);
+                let c = self.name_at(d, i, &mut n).map_err(e)?;
+                match list::path(d, &n[0..c], &mut p) {
+                    Some(c) if self.hide.add(&p[0..c]) => {
+                        // The save failed, so go back to the list on the SD Card
+                        // and light up all the LEDs, like when the list is full.
+                        if self.hide.save(self.root, FILE_HIDDEN).is_err() {
+                            self.hide = NameList::load(self.root, FILE_HIDDEN);
+                            l.all_on();
+                            self.board.sleep(2_000);
+                            return Ok(sel);
+                        }
+                        l.network.on();
+                        self.board.sleep(1_000);
+                    },
+                    // The path or list is full.
+                    _ => {
+                        l.all_on();
+                        self.board.sleep(2_000);
+                        return Ok(sel);
+                    },
+                }
+                // The hidden entry is now filtered out, so the same position is
+                // the next image. Continue on to display it.
+            },
+            Action::Status => {
+                let v = !self.state.status();
+                self.state.set_status(v);
+                // Only one of the modes can be on.
+                self.state.set_countdown(false);
+                self.flash_state(l, v);
+                a = Action::Refresh;
+            },
+            Action::Countdown => {
+                let v = !self.state.countdown();
+                self.state.set_countdown(v);
+                self.state.set_status(false);
+                self.flash_state(l, v);
+                a = Action::Refresh;
+            },
+            Action::StatusNext | Action::StatusPrev if !self.state.status() => return Ok(sel),
+            Action::ShowQr => {
+                l.network.on();
+                self.qr()?;
+                l.activity.off();
+                self.inky.update();
+                // The badge is shown again on the next press or wake-up.
+                return Ok(sel);
+            },
+            Action::ShowBarcode => {
+                l.network.on();
+                self.barcode_screen();
+                l.activity.off();
+                self.inky.update();
+                return Ok(sel);
+            },
+            Action::ShowMoon => {
+                l.network.on();
+                self.moon_screen();
+                l.activity.off();
+                self.inky.update();
+                return Ok(sel);
+            },
+            Action::Reset => {
+                // Let the user know the reset was received.
+                l.all_on();
+                self.board.sleep(2_000);
+                l.all_off();
+                l.activity.on();
+            },
             Action::Rand if sel == 0 => {
                 l.all_on();
                 self.board.sleep(2_000);
@@ -388,15 +1652,55 @@ impl<'a, const B: usize, const W: u16, const H: u16, D: BlockDevice> SideShow<'a
             },
             _ => (),
         }
-        self.background()?;
+        self.background(&a)?;
         l.network.on();
-        let r = self.badge(a, sel).map_err(SideError::badge)?;
+        let r = if self.state.status() {
+            self.status(match a {
+                Action::StatusNext => 1,
+                Action::StatusPrev => -1,
+                _ => 0,
+            });
+            // The badge isn't changed when on the status board.
+            sel
+        } else if self.state.countdown() {
+            self.countdown();
+            sel
+        } else if DATES && sel & 0x80 == 0 && matches!(a, Action::Wake | Action::Refresh) && self.dated() {
+            // The badge isn't changed while the date image is shown, the buttons
+            // still go back to the Badges.
+            sel
+        } else {
+            let r = self.badge(a, sel).map_err(SideError::badge)?;
+            self.caption(r);
+            self.barcode_overlay(r);
+            r
+        };
+        self.clock();
+        self.moon();
+        if BATTERY_ICON {
+            let w = self.inky.width() as i32;
+            if self.vbus {
+                draw::charging(&mut self.inky, w - 64, 8, 8);
+                draw::battery(&mut self.inky, w - 48, 8, 32, 100);
+            } else {
+                draw::battery(&mut self.inky, w - 48, 8, 32, power::level(self.volt, BATTERY_EMPTY, BATTERY_FULL));
+            }
+        }
         l.activity.off();
         self.inky.update();
         Ok(r)
     }
 }
 
+#[inline]
+fn unit(s: &mut TextBuf<256>, n: u32, v: &str) {
+    let _ = write!(s, "{n} {v}{}", if n == 1 { "" } else { "s" });
+}
+#[inline]
+fn filter<'b>(dir: &'b str, s: &State, favs: &'b NameList, hide: &'b NameList) -> Filter<'b> {
+    Filter::new(dir, if s.favorites() && (dir == DIR_BADGES || dir == DIR_BADGES_NIGHT) { Some(favs) } else { None }, hide)
+}
+
 impl From<ImageError> for LoadError {
     #[inline(always)]
     fn from(v: ImageError) -> LoadError {
@@ -0,0 +1,174 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+
+use crate::art::isqrt;
+use crate::calendar::Date;
+
+/// Fixed point one, all sine and cosine values are scaled by this.
+pub const ONE: i32 = 16_384i32;
+/// Tilt of the Earth (in millidegrees).
+const TILT: i32 = 23_440i32;
+/// Altitude (in millidegrees) of the sun's center at sunrise and sunset, as
+/// the light is bent by the atmosphere.
+const HORIZON: i32 = -833i32;
+
+/// Average length (in seconds) of a lunar month.
+const SYNODIC: i64 = 2_551_443i64;
+/// A known new moon (2000-01-06 18:14 UTC), in seconds since 2000-01-01.
+const NEW_MOON: i64 = 497_640i64;
+
+const PHASES: [&str; 8] = [
+    "New Moon",
+    "Waxing Crescent",
+    "First Quarter",
+    "Waxing Gibbous",
+    "Full Moon",
+    "Waning Gibbous",
+    "Last Quarter",
+    "Waning Crescent",
+];
+
+/// Sine of each whole degree from 0 to 90.
+const SINE: [i32; 91] = [
+    0, 286, 572, 857, 1143, 1428, 1713, 1997, 2280, 2563, 2845, 3126, 3406, 3686, 3964, 4240, 4516, 4790, 5063, 5334, 5604, 5872, 6138, 6402, 6664, 6924, 7182,
+    7438, 7692, 7943, 8192, 8438, 8682, 8923, 9162, 9397, 9630, 9860, 10087, 10311, 10531, 10749, 10963, 11174, 11381, 11585, 11786, 11982, 12176, 12365,
+    12551, 12733, 12911, 13085, 13255, 13421, 13583, 13741, 13894, 14044, 14189, 14330, 14466, 14598, 14726, 14849, 14968, 15082, 15191, 15296, 15396, 15491,
+    15582, 15668, 15749, 15826, 15897, 15964, 16026, 16083, 16135, 16182, 16225, 16262, 16294, 16322, 16344, 16362, 16374, 16382, 16384,
+];
+
+/// Phase of the moon, from the average lunar month. This can be up to about
+/// half a day off from the real phase, which is close enough to name it.
+pub struct Moon {
+    age: i64,
+}
+pub enum Sun {
+    /// Sunrise and sunset, in seconds since 2000-01-01 00:00:00 UTC.
+    Times(i64, i64),
+    /// The sun does not set on this day.
+    Up,
+    /// The sun does not rise on this day.
+    Down,
+}
+
+impl Moon {
+    /// Returns the Moon at 't' (in seconds since 2000-01-01 00:00:00 UTC).
+    #[inline]
+    pub fn at(t: i64) -> Moon {
+        Moon { age: (t - NEW_MOON).rem_euclid(SYNODIC) }
+    }
+
+    /// Returns the name of the phase.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        // Each phase is centered on it's point, so the quarters are a few
+        // days long instead of an instant.
+        PHASES[(((self.age * 16i64) / SYNODIC + 1i64) / 2i64 % 8i64) as usize]
+    }
+    /// Returns the phase angle (in millidegrees), zero is a new moon and
+    /// 180,000 is a full moon.
+    #[inline]
+    pub fn angle(&self) -> i32 {
+        ((self.age * 360_000i64) / SYNODIC) as i32
+    }
+    /// Returns the percent (0 - 100) of the moon that is lit.
+    #[inline]
+    pub fn lit(&self) -> u8 {
+        (((ONE - cos(self.angle())) * 100i32) / (ONE * 2i32)) as u8
+    }
+}
+
+/// Returns the sunrise and sunset for the date 'd' at the latitude 'lat' and
+/// longitude 'lon' (in millidegrees, with North and East positive).
+///
+/// Based on the "Sunrise equation", which is accurate to a minute or two away
+/// from the poles.
+pub fn sun(d: &Date, lat: i32, lon: i32) -> Sun {
+    // Solar noon (in seconds) at the longitude, with the time between UTC and
+    // the terrestrial time.
+    let j = d.days() as i64 * 86_400i64 + 43_200i64 + 69i64 - (lon as i64 * 86_400i64) / 360_000i64;
+    // Mean anomaly (in millidegrees), J2000 is at noon.
+    let m = (357_529i64 + ((j - 43_200i64) * 98_560_028i64) / 8_640_000_000i64).rem_euclid(360_000i64) as i32;
+    // Equation of the center, then the ecliptic longitude.
+    let c = (19_148i32 * sin(m) + 200i32 * sin(m * 2) + 3i32 * sin(m * 3)) / (10i32 * ONE);
+    let l = (m + c + 282_937i32).rem_euclid(360_000i32);
+    let t = j + ((458i32 * sin(m) - 596i32 * sin(l * 2)) / ONE) as i64;
+    // Declination of the sun.
+    let s = (sin(l) * sin(TILT)) / ONE;
+    let k = isqrt(ONE * ONE - s * s);
+    let (a, b) = (sin(lat), cos(lat));
+    let q = (b * k) / ONE;
+    if q <= 0 {
+        // At the poles, it's up all the time or not at all.
+        return if (a > 0) == (s > 0) { Sun::Up } else { Sun::Down };
+    }
+    // Hour angle of sunrise and sunset.
+    let h = ((sin(HORIZON) - (a * s) / ONE) * ONE) / q;
+    if h >= ONE {
+        return Sun::Down;
+    }
+    if h <= -ONE {
+        return Sun::Up;
+    }
+    let w = (acos(h) as i64 * 6i64) / 25i64;
+    Sun::Times(t - w, t + w)
+}
+/// Returns the sine of 'a' (in millidegrees).
+pub fn sin(a: i32) -> i32 {
+    let a = a.rem_euclid(360_000i32);
+    match a / 90_000i32 {
+        0 => quarter(a),
+        1 => quarter(180_000i32 - a),
+        2 => -quarter(a - 180_000i32),
+        _ => -quarter(360_000i32 - a),
+    }
+}
+/// Returns the cosine of 'a' (in millidegrees).
+#[inline]
+pub fn cos(a: i32) -> i32 {
+    sin(a.rem_euclid(360_000i32) + 90_000i32)
+}
+/// Returns the angle (in millidegrees, 0 - 180,000) with the cosine 'v'.
+pub fn acos(v: i32) -> i32 {
+    // The cosine only goes down between 0 and 180, so just search for it.
+    let (mut x, mut y) = (0i32, 180_000i32);
+    while x < y {
+        let m = (x + y) / 2;
+        if cos(m) > v {
+            x = m + 1;
+        } else {
+            y = m;
+        }
+    }
+    x
+}
+
+/// Returns the sine of 'a' (in millidegrees, 0 - 90,000) using the table.
+#[inline]
+fn quarter(a: i32) -> i32 {
+    let (i, f) = ((a / 1_000i32) as usize, a % 1_000i32);
+    if i >= 90 {
+        return ONE;
+    }
+    // Linear between the whole degrees.
+    SINE[i] + ((SINE[i + 1] - SINE[i]) * f) / 1_000i32
+}
@@ -0,0 +1,250 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::cmp::min;
+use core::result::Result::{self, Ok};
+
+use inky_frame::fs::{BlockDevice, DeviceError, Volume};
+
+use crate::files;
+
+/// First byte of a valid state file.
+const MAGIC: u8 = 0x53u8;
+/// Size of the state file, in bytes.
+const SIZE: usize = 14usize;
+
+const FLAG_SECURE: u8 = 0x01u8;
+const FLAG_BG_LOCK: u8 = 0x02u8;
+const FLAG_BG_SEQUENCE: u8 = 0x04u8;
+const FLAG_FAVORITES: u8 = 0x08u8;
+const FLAG_COVER: u8 = 0x10u8;
+const FLAG_STATUS: u8 = 0x20u8;
+const FLAG_COUNTDOWN: u8 = 0x40u8;
+
+/// Settings that need to last between power cycles, but are too large to fit
+/// in the single PCF byte.
+///
+/// The state is stored on the SD Card as a small binary file. Any missing
+/// values (from older or damaged files) are set to their defaults, so new
+/// values should always be added to the end.
+pub struct State {
+    flags:  u8,
+    expire: u32,
+    bg:     u16,
+    status: u8,
+    seed:   u32,
+    dirty:  bool,
+}
+
+impl State {
+    #[inline(always)]
+    pub const fn new() -> State {
+        State {
+            flags:  0u8,
+            expire: 0u32,
+            bg:     0u16,
+            status: 0u8,
+            seed:   0u32,
+            dirty:  false,
+        }
+    }
+
+    /// Load the State from the file at 'path'. If the file does not exist or
+    /// is invalid, the default State is returned.
+    pub fn load<D: BlockDevice>(v: &Volume<'_, D>, path: &str) -> State {
+        let mut b = [0u8; SIZE];
+        let n = match files::read(v, path, &mut b) {
+            Ok(n) if n > 0 && b[0] == MAGIC => min(n, SIZE),
+            _ => return State::new(),
+        };
+        let mut s = State::new();
+        if n > 1 {
+            s.flags = b[1];
+        }
+        if n > 6 {
+            s.expire = u32::from_le_bytes([b[3], b[4], b[5], b[6]]);
+        }
+        if n > 8 {
+            s.bg = u16::from_le_bytes([b[7], b[8]]);
+        }
+        if n > 9 {
+            s.status = b[9];
+        }
+        if n > 13 {
+            s.seed = u32::from_le_bytes([b[10], b[11], b[12], b[13]]);
+        }
+        s
+    }
+
+    /// Write the State to the file at 'path', only if it was changed.
+    pub fn save<D: BlockDevice>(&mut self, v: &Volume<'_, D>, path: &str) -> Result<(), DeviceError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let (e, g) = (self.expire.to_le_bytes(), self.bg.to_le_bytes());
+        let r = self.seed.to_le_bytes();
+        files::write(v, path, &[
+            MAGIC,
+            self.flags,
+            // Unused, the PIN progress is only kept in memory.
+            0u8,
+            e[0],
+            e[1],
+            e[2],
+            e[3],
+            g[0],
+            g[1],
+            self.status,
+            r[0],
+            r[1],
+            r[2],
+            r[3],
+        ])?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Returns the index of the last background image displayed.
+    #[inline(always)]
+    pub fn background(&self) -> u16 {
+        self.bg
+    }
+    /// Returns the seed used to draw the last generated background.
+    #[inline(always)]
+    pub fn background_seed(&self) -> u32 {
+        self.seed
+    }
+    #[inline(always)]
+    pub fn background_lock(&self) -> bool {
+        self.flags & FLAG_BG_LOCK != 0
+    }
+    #[inline(always)]
+    pub fn background_sequence(&self) -> bool {
+        self.flags & FLAG_BG_SEQUENCE != 0
+    }
+    /// Returns true if the countdown is displayed instead of the badges.
+    #[inline(always)]
+    pub fn countdown(&self) -> bool {
+        self.flags & FLAG_COUNTDOWN != 0
+    }
+    /// Returns true if the display is covered and should not be changed.
+    #[inline(always)]
+    pub fn cover(&self) -> bool {
+        self.flags & FLAG_COVER != 0
+    }
+    /// Returns true if only favorite badges should be selected.
+    #[inline(always)]
+    pub fn favorites(&self) -> bool {
+        self.flags & FLAG_FAVORITES != 0
+    }
+    /// Returns true if the status board is displayed instead of the badges.
+    #[inline(always)]
+    pub fn status(&self) -> bool {
+        self.flags & FLAG_STATUS != 0
+    }
+    /// Returns the index of the selected status entry.
+    #[inline(always)]
+    pub fn status_index(&self) -> u8 {
+        self.status
+    }
+    /// Returns the time the lock expires (in seconds since 2000-01-01), or
+    /// zero if it does not expire.
+    #[inline(always)]
+    pub fn expire(&self) -> u32 {
+        self.expire
+    }
+    #[inline(always)]
+    pub fn secure(&self) -> bool {
+        self.flags & FLAG_SECURE != 0
+    }
+    /// Set the current background. It's saved even in random mode, so the
+    /// lock, cover and hide actions know what is on the display after the
+    /// device was powered off.
+    #[inline]
+    pub fn set_background(&mut self, v: u16) {
+        if self.bg != v {
+            self.bg = v;
+            self.dirty = true;
+        }
+    }
+    /// Set the seed of the current generated background.
+    #[inline]
+    pub fn set_background_seed(&mut self, v: u32) {
+        if self.seed != v {
+            self.seed = v;
+            self.dirty = true;
+        }
+    }
+    #[inline]
+    pub fn set_background_lock(&mut self, v: bool) {
+        self.set_flag(FLAG_BG_LOCK, v);
+    }
+    #[inline]
+    pub fn set_background_sequence(&mut self, v: bool) {
+        self.set_flag(FLAG_BG_SEQUENCE, v);
+    }
+    #[inline]
+    pub fn set_countdown(&mut self, v: bool) {
+        self.set_flag(FLAG_COUNTDOWN, v);
+    }
+    #[inline]
+    pub fn set_cover(&mut self, v: bool) {
+        self.set_flag(FLAG_COVER, v);
+    }
+    #[inline]
+    pub fn set_status(&mut self, v: bool) {
+        self.set_flag(FLAG_STATUS, v);
+    }
+    #[inline]
+    pub fn set_status_index(&mut self, v: u8) {
+        if self.status != v {
+            self.status = v;
+            self.dirty = true;
+        }
+    }
+    #[inline]
+    pub fn set_expire(&mut self, v: u32) {
+        if self.expire != v {
+            self.expire = v;
+            self.dirty = true;
+        }
+    }
+    #[inline]
+    pub fn set_favorites(&mut self, v: bool) {
+        self.set_flag(FLAG_FAVORITES, v);
+    }
+    #[inline]
+    pub fn set_secure(&mut self, v: bool) {
+        self.set_flag(FLAG_SECURE, v);
+    }
+
+    #[inline]
+    fn set_flag(&mut self, f: u8, v: bool) {
+        let n = if v { self.flags | f } else { self.flags & !f };
+        if n != self.flags {
+            self.flags = n;
+            self.dirty = true;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::cmp::Ord;
+use core::iter::{IntoIterator, Iterator};
+use core::option::Option::{self, None, Some};
+
+use inky_frame::frame::Color;
+
+use crate::draw::Canvas;
+use crate::text::{Align, Font, TextBuf};
+
+/// Field in the template layout.
+///
+/// Each layout line is "name x y width height [scale] [color] [align]", with
+/// the values separated by spaces. The name is the CSV column to draw and the
+/// scale is the largest text scale to use, it's made smaller if the text does
+/// not fit in the box.
+struct Entry<'a> {
+    name:  &'a str,
+    x:     i32,
+    y:     i32,
+    w:     u16,
+    h:     u16,
+    scale: u16,
+    color: Color,
+    align: Align,
+}
+/// Iterator over the fields of a CSV line. Quoted fields keep their quotes.
+struct Fields<'a> {
+    s:    &'a str,
+    done: bool,
+}
+
+impl<'a> Entry<'a> {
+    fn parse(s: &'a str) -> Option<Entry<'a>> {
+        let mut v = s.split_whitespace();
+        let name = v.next()?;
+        let (x, y) = (v.next()?.parse::<i32>().ok()?, v.next()?.parse::<i32>().ok()?);
+        let (w, h) = (v.next()?.parse::<u16>().ok()?, v.next()?.parse::<u16>().ok()?);
+        let scale = v.next().map_or(Some(2u16), |e| e.parse::<u16>().ok())?;
+        let color = v.next().map_or(Some(Color::Black), color)?;
+        let align = match v.next() {
+            None => Align::Left,
+            Some(e) if e.eq_ignore_ascii_case("left") => Align::Left,
+            Some(e) if e.eq_ignore_ascii_case("center") => Align::Center,
+            Some(e) if e.eq_ignore_ascii_case("right") => Align::Right,
+            Some(_) => return None,
+        };
+        Some(Entry {
+            name,
+            x,
+            y,
+            w,
+            h,
+            scale,
+            color,
+            align,
+        })
+    }
+}
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.done {
+            return None;
+        }
+        let mut q = false;
+        for (i, c) in self.s.bytes().enumerate() {
+            match c {
+                b'"' => q = !q,
+                b',' if !q => {
+                    let v = &self.s[0..i];
+                    self.s = &self.s[i + 1..];
+                    return Some(v);
+                },
+                _ => (),
+            }
+        }
+        self.done = true;
+        Some(self.s)
+    }
+}
+
+/// Draw the fields of the CSV line 'row' using the 'layout' text, which has
+/// one Entry per line. Fields are matched to the layout using the names in
+/// the CSV 'header' line.
+///
+/// Layout lines that are blank, start with '#' or can't be parsed are skipped,
+/// as are layout names that are not in the header.
+pub fn render(c: &mut impl Canvas, f: &Font, layout: &str, header: &str, row: &str) {
+    for l in layout.lines() {
+        let l = l.trim();
+        if l.is_empty() || l.starts_with('#') {
+            continue;
+        }
+        let e = match Entry::parse(l) {
+            Some(e) => e,
+            None => continue,
+        };
+        let v = match fields(header).position(|v| unquote(v).eq_ignore_ascii_case(e.name)) {
+            Some(i) => fields(row).nth(i).unwrap_or_default(),
+            None => continue,
+        };
+        let mut t = TextBuf::<256>::new();
+        // Quotes inside quoted fields are doubled.
+        for (i, x) in unquote(v).split("\"\"").enumerate() {
+            if i > 0 {
+                t.push("\"");
+            }
+            t.push(x);
+        }
+        let mut s = e.scale.max(1);
+        while s > 1 && f.height(t.as_str(), e.w, s) > e.h {
+            s -= 1;
+        }
+        f.draw_box(c, e.x, e.y, e.w, e.h, t.as_str(), s, e.color, &e.align);
+    }
+}
+
+#[inline(always)]
+fn fields(s: &str) -> Fields<'_> {
+    Fields { s, done: false }
+}
+#[inline]
+fn unquote(v: &str) -> &str {
+    let v = v.trim();
+    v.strip_prefix('"').and_then(|x| x.strip_suffix('"')).unwrap_or(v)
+}
+fn color(v: &str) -> Option<Color> {
+    let c = [
+        ("black", Color::Black),
+        ("white", Color::White),
+        ("green", Color::Green),
+        ("blue", Color::Blue),
+        ("red", Color::Red),
+        ("yellow", Color::Yellow),
+        ("orange", Color::Orange),
+    ];
+    c.into_iter().find(|x| x.0.eq_ignore_ascii_case(v)).map(|x| x.1)
+}
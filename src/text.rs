@@ -0,0 +1,502 @@
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//
+
+#![no_implicit_prelude]
+
+extern crate core;
+extern crate inky_frame;
+
+use core::cell::UnsafeCell;
+use core::cmp::Ord;
+use core::fmt::{self, Write};
+use core::iter::Iterator;
+use core::marker::Sync;
+use core::option::Option::{self, None, Some};
+use core::result::Result::{Err, Ok};
+use core::str::{from_utf8, from_utf8_unchecked};
+
+use inky_frame::fs::{BlockDevice, Volume};
+use inky_frame::frame::Color;
+
+use crate::draw::{self, Canvas, Position};
+use crate::files;
+
+/// Width of a glyph in the built-in font, in pixels.
+const GLYPH_WIDTH: u16 = 5u16;
+/// Height of a glyph in the built-in font, in pixels.
+const GLYPH_HEIGHT: u16 = 7u16;
+
+/// Max size of a font file that can be loaded, in bytes. This is large enough
+/// for a 16x32 font with 256 glyphs and it's unicode table.
+const FONT_SIZE: usize = 20_480usize;
+
+const PSF2_MAGIC: u32 = 0x864A_B572u32;
+const PSF2_HAS_TABLE: u32 = 0x01u32;
+
+static BUFFER: FontBuffer = FontBuffer(UnsafeCell::new([0u8; FONT_SIZE]));
+
+/// Built-in 5x7 font covering printable ASCII (0x20 - 0x7E). Each glyph is
+/// five columns, with the top row in the lowest bit.
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x55, 0x22, 0x50], // '&'
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '''
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // ')'
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // '*'
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // '0'
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // '@'
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // 'A'
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // 'D'
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // 'F'
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // 'G'
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // 'H'
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // 'J'
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // 'M'
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // 'N'
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // 'O'
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // 'Q'
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // 'T'
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // 'U'
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // 'V'
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x07, 0x08, 0x70, 0x08, 0x07], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\'
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // 'f'
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // 'g'
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // 'j'
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // 'k'
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // 'l'
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // 'q'
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // 't'
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // 'u'
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // 'v'
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // 'y'
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x08, 0x04, 0x08, 0x10, 0x08], // '~'
+];
+
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+pub enum Font {
+    Builtin,
+    Psf(Psf),
+}
+
+/// Fixed size buffer used to build strings without allocating. Anything
+/// written past the end of the buffer is dropped.
+pub struct TextBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+/// PC Screen Font (version 2) loaded from the SD Card.
+pub struct Psf {
+    buf:    &'static [u8],
+    count:  usize,
+    size:   usize,
+    start:  usize,
+    table:  usize,
+    width:  u16,
+    height: u16,
+}
+
+struct FontBuffer(UnsafeCell<[u8; FONT_SIZE]>);
+
+enum Glyph<'a> {
+    Builtin(&'a [u8; 5]),
+    Psf(&'a [u8], u16),
+}
+
+impl Font {
+    /// Load a PSF2 font from the file at 'path'. If the file can't be read or
+    /// is not a valid PSF2 font, the built-in font is returned.
+    ///
+    /// NOTE(sf): The font is read into a static buffer to keep it off the
+    ///           stack, so only one font can be loaded and this should only
+    ///           be called once.
+    pub fn load<D: BlockDevice>(v: &Volume<'_, D>, path: &str) -> Font {
+        // SAFETY: This is only called once at startup, so the returned Font
+        //         holds the only reference to the buffer.
+        let b = unsafe { &mut *BUFFER.0.get() };
+        match files::read(v, path, b) {
+            Ok(n) => Psf::parse(&b[0..n]).map_or(Font::Builtin, Font::Psf),
+            Err(_) => Font::Builtin,
+        }
+    }
+
+    /// Returns the distance between the start of each line, in pixels.
+    #[inline]
+    pub fn line_height(&self, scale: u16) -> u16 {
+        match self {
+            Font::Builtin => (GLYPH_HEIGHT + 2).saturating_mul(scale),
+            Font::Psf(f) => f.height.saturating_mul(scale),
+        }
+    }
+    /// Returns the width (in pixels) of the string 's' when drawn at the scale
+    /// 'scale'. Only the first line is measured.
+    #[inline]
+    pub fn width(&self, s: &str, scale: u16) -> u16 {
+        // Large fonts, scales or strings stop at the max size instead of
+        // overflowing.
+        let n = first_line(s).count().min(0xFFFF) as u16;
+        match self {
+            Font::Builtin if n > 0 => (n.saturating_mul(GLYPH_WIDTH + 1) - 1).saturating_mul(scale),
+            Font::Builtin => 0,
+            Font::Psf(f) => n.saturating_mul(f.width).saturating_mul(scale),
+        }
+    }
+    /// Returns the height (in pixels) of the string 's' when wrapped to the
+    /// width 'w' and drawn at the scale 'scale'.
+    #[inline]
+    pub fn height(&self, s: &str, w: u16, scale: u16) -> u16 {
+        self.size(s, w, scale).1
+    }
+    /// Returns the width of the longest line and the height (in pixels) of the
+    /// string 's' when wrapped to the width 'w' and drawn at the scale 'scale'.
+    pub fn size(&self, s: &str, w: u16, scale: u16) -> (u16, u16) {
+        let (mut r, mut n, mut m) = (s, 0u16, 0u16);
+        while !r.is_empty() {
+            let (t, x) = self.line(r, w, scale);
+            m = m.max(self.width(t, scale));
+            r = x;
+            n = n.saturating_add(1);
+        }
+        (m, n.saturating_mul(self.line_height(scale)))
+    }
+    /// Draw the first line of the string 's' with it's top left corner at
+    /// (x, y). Each pixel of the font is drawn as a 'scale' by 'scale' square.
+    ///
+    /// Returns the X position after the last glyph.
+    pub fn draw(&self, c: &mut impl Canvas, x: i32, y: i32, s: &str, scale: u16, v: Color) -> i32 {
+        let k = scale.max(1);
+        let mut p = x;
+        for i in first_line(s) {
+            p += self.glyph(c, p, y, i, k, v) as i32;
+        }
+        p
+    }
+    /// Draw the string 's' inside the box at (x, y) with the size (w, h). The
+    /// string is wrapped at spaces (or inside words if they're too long) and
+    /// on newlines. Each line is aligned inside the box by 'a'. Any lines that
+    /// don't fit in the box are not drawn.
+    ///
+    /// Returns the height of the drawn lines.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_box(&self, c: &mut impl Canvas, x: i32, y: i32, w: u16, h: u16, s: &str, scale: u16, v: Color, a: &Align) -> u16 {
+        let (l, mut r, mut p) = (self.line_height(scale), s, 0u16);
+        while !r.is_empty() && p.saturating_add(l) <= h {
+            let (t, n) = self.line(r, w, scale);
+            let o = match a {
+                Align::Left => 0,
+                Align::Center => (w.saturating_sub(self.width(t, scale)) / 2) as i32,
+                Align::Right => w.saturating_sub(self.width(t, scale)) as i32,
+            };
+            self.draw(c, x + o, y + p as i32, t, scale, v);
+            r = n;
+            p += l;
+        }
+        p
+    }
+
+    /// Split the string 's' into the first line that fits in the width 'w' and
+    /// the rest of the string.
+    fn line<'s>(&self, s: &'s str, w: u16, scale: u16) -> (&'s str, &'s str) {
+        let s = s.trim_start_matches(' ');
+        let (mut t, mut b) = (0u16, 0usize);
+        for (i, c) in s.char_indices() {
+            if c == '\n' {
+                return (&s[0..i], &s[i + 1..]);
+            }
+            let n = self.advance(scale);
+            if t.saturating_add(n) > w.saturating_add(self.spacing(scale)) {
+                return match b {
+                    // Break inside the word, but always take at least one
+                    // character so we don't get stuck.
+                    0 if i == 0 => s.split_at(c.len_utf8()),
+                    0 => s.split_at(i),
+                    _ => (s[0..b].trim_end(), &s[b..]),
+                };
+            }
+            if c == ' ' {
+                b = i;
+            }
+            t = t.saturating_add(n);
+        }
+        (s, "")
+    }
+    #[inline]
+    fn advance(&self, scale: u16) -> u16 {
+        match self {
+            Font::Builtin => (GLYPH_WIDTH + 1).saturating_mul(scale),
+            Font::Psf(f) => f.width.saturating_mul(scale),
+        }
+    }
+    #[inline]
+    fn spacing(&self, scale: u16) -> u16 {
+        match self {
+            Font::Builtin => scale,
+            Font::Psf(_) => 0,
+        }
+    }
+    fn glyph(&self, c: &mut impl Canvas, x: i32, y: i32, ch: char, scale: u16, v: Color) -> u16 {
+        let (g, w, h) = match self {
+            Font::Psf(f) => match f.glyph(ch).or_else(|| f.glyph('?')) {
+                Some(g) => (Glyph::Psf(g, f.width.div_ceil(8)), f.width, f.height),
+                None => (builtin(ch), GLYPH_WIDTH, GLYPH_HEIGHT),
+            },
+            Font::Builtin => (builtin(ch), GLYPH_WIDTH, GLYPH_HEIGHT),
+        };
+        for j in 0..h {
+            for i in 0..w {
+                if g.is_set(i, j) {
+                    c.rect(x + i as i32 * scale as i32, y + j as i32 * scale as i32, scale, scale, v);
+                }
+            }
+        }
+        self.advance(scale)
+    }
+}
+impl<const N: usize> TextBuf<N> {
+    #[inline(always)]
+    pub const fn new() -> TextBuf<N> {
+        TextBuf { buf: [0u8; N], len: 0usize }
+    }
+
+    #[inline(always)]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: Only whole 'str' characters are ever added to the buffer.
+        unsafe { from_utf8_unchecked(&self.buf[0..self.len]) }
+    }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn push(&mut self, s: &str) {
+        // Only add whole characters, so the buffer is always valid UTF-8.
+        let mut n = s.len().min(N - self.len);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[0..n]);
+        self.len += n;
+    }
+}
+impl Psf {
+    fn parse(b: &'static [u8]) -> Option<Psf> {
+        if b.len() < 32 || u32_at(b, 0) != PSF2_MAGIC {
+            return None;
+        }
+        let (start, flags, count, size) = (u32_at(b, 8) as usize, u32_at(b, 12), u32_at(b, 16) as usize, u32_at(b, 20) as usize);
+        let (height, width) = (u32_at(b, 24) as u16, u32_at(b, 28) as u16);
+        // The header values come from the SD Card, a bad file shouldn't be
+        // able to overflow.
+        let table = count.checked_mul(size).and_then(|v| v.checked_add(start))?;
+        // Make sure all the glyphs were loaded, and that they're as large as
+        // they say they are.
+        if width == 0 || height == 0 || table > b.len() || size < width.div_ceil(8) as usize * height as usize {
+            return None;
+        }
+        Some(Psf {
+            buf: b,
+            count,
+            size,
+            start,
+            table: if flags & PSF2_HAS_TABLE != 0 { table } else { 0 },
+            width,
+            height,
+        })
+    }
+
+    fn glyph(&self, c: char) -> Option<&[u8]> {
+        let i = if self.table == 0 { c as usize } else { self.lookup(c)? };
+        if i >= self.count {
+            return None;
+        }
+        let s = self.start + i * self.size;
+        Some(&self.buf[s..s + self.size])
+    }
+    fn lookup(&self, c: char) -> Option<usize> {
+        let mut e = [0u8; 4];
+        let v = c.encode_utf8(&mut e).as_bytes();
+        let (mut i, mut p, mut q) = (0usize, self.table, false);
+        // Each glyph entry is a list of UTF-8 characters, followed by any
+        // multi-character sequences (each starting with 0xFE) and ends with
+        // 0xFF. We ignore the sequences.
+        while p < self.buf.len() && i < self.count {
+            match self.buf[p] {
+                0xFF => {
+                    i += 1;
+                    q = false;
+                    p += 1;
+                },
+                0xFE => {
+                    q = true;
+                    p += 1;
+                },
+                x => {
+                    let n = match x {
+                        0x00..=0x7F => 1,
+                        0xC0..=0xDF => 2,
+                        0xE0..=0xEF => 3,
+                        _ => 4,
+                    };
+                    if !q && self.buf[p..].starts_with(v) && n == v.len() {
+                        return Some(i);
+                    }
+                    p += n;
+                },
+            }
+        }
+        None
+    }
+}
+impl Glyph<'_> {
+    #[inline]
+    fn is_set(&self, x: u16, y: u16) -> bool {
+        match self {
+            Glyph::Builtin(g) => (g[x as usize] >> y) & 1 == 1,
+            Glyph::Psf(g, r) => {
+                let i = (y * r + x / 8) as usize;
+                i < g.len() && (g[i] >> (7 - (x % 8))) & 1 == 1
+            },
+        }
+    }
+}
+
+impl<const N: usize> Write for TextBuf<N> {
+    #[inline(always)]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push(s);
+        Ok(())
+    }
+}
+
+unsafe impl Sync for FontBuffer {}
+
+/// Draw the string 's' as a label at the Position 'p' on the display. If 'bg'
+/// is not None, a box of that color is drawn behind the text. Long strings are
+/// wrapped to fit the display width.
+pub fn label(c: &mut impl Canvas, f: &Font, s: &str, p: &Position, scale: u16, v: Color, bg: Option<Color>) {
+    if s.is_empty() {
+        return;
+    }
+    // Padding inside the box.
+    let d = scale.max(1).saturating_mul(2);
+    let (w, h) = f.size(s, c.width().saturating_sub(draw::MARGIN.saturating_add(d).saturating_mul(2)), scale);
+    let (bw, bh) = (w.saturating_add(d.saturating_mul(2)), h.saturating_add(d.saturating_mul(2)));
+    let (x, y) = draw::place(c, p, bw, bh);
+    let a = match p {
+        Position::TopLeft | Position::BottomLeft => Align::Left,
+        Position::TopRight | Position::BottomRight => Align::Right,
+        _ => Align::Center,
+    };
+    if let Some(b) = bg {
+        c.rect(x, y, bw, bh, b);
+    }
+    f.draw_box(c, x + d as i32, y + d as i32, w, h, s, scale, v, &a);
+}
+/// Returns the bytes in 'b' as a string, dropping everything after the first
+/// invalid UTF-8 sequence, which may be a character cut off by a buffer size.
+#[inline]
+pub fn valid(b: &[u8]) -> &str {
+    match from_utf8(b) {
+        Ok(v) => v,
+        // SAFETY: Everything before 'valid_up_to' is valid UTF-8.
+        Err(e) => unsafe { from_utf8_unchecked(&b[0..e.valid_up_to()]) },
+    }
+}
+
+#[inline]
+fn builtin(c: char) -> Glyph<'static> {
+    match c as u32 {
+        0x20..=0x7E => Glyph::Builtin(&GLYPHS[(c as u32 - 0x20) as usize]),
+        _ => Glyph::Builtin(&GLYPHS[(b'?' - 0x20) as usize]),
+    }
+}
+#[inline]
+fn first_line(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().take_while(|c| *c != '\n')
+}
+#[inline]
+fn u32_at(b: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]])
+}